@@ -0,0 +1,161 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use crate::detect::ScreenshotMatcher;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScanReason {
+    Startup,
+    Resume,
+}
+
+/// Screenshots found on disk that still carry their tool-generated names.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CatchUpReport {
+    pub reason: ScanReason,
+    pub scanned_at: DateTime<Utc>,
+    pub files: Vec<PathBuf>,
+}
+
+/// Lists screenshots in `dirs` that were never renamed, oldest first.
+/// Files last modified more than `max_age` ago are ignored; `None` means no limit.
+pub fn find_unnamed_screenshots(
+    dirs: &[PathBuf],
+    matcher: &ScreenshotMatcher,
+    max_age: Option<Duration>,
+) -> Vec<PathBuf> {
+    let now = SystemTime::now();
+    let mut found: Vec<(SystemTime, PathBuf)> = Vec::new();
+
+    for dir in dirs {
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                eprintln!("❌ Failed to scan {}: {}", dir.display(), e);
+                continue;
+            }
+        };
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if !matcher.matches(&path) {
+                continue;
+            }
+            let Some(modified) = modified_time(&path) else {
+                continue;
+            };
+            if let Some(max_age) = max_age {
+                let age = now.duration_since(modified).unwrap_or(Duration::ZERO);
+                if age > max_age {
+                    continue;
+                }
+            }
+            found.push((modified, path));
+        }
+    }
+
+    found.sort();
+    found.into_iter().map(|(_, path)| path).collect()
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    let metadata = fs::metadata(path).ok()?;
+    if !metadata.is_file() {
+        return None;
+    }
+    metadata.modified().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detect::{DetectionRules, UserPattern};
+    use std::fs::File;
+
+    fn setup(dir: &str) -> PathBuf {
+        let dir = PathBuf::from("tests/assets").join(dir);
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Writes `name` in `dir`, last modified `age` ago.
+    fn touch(dir: &Path, name: &str, age: Duration) -> PathBuf {
+        let path = dir.join(name);
+        let file = File::create(&path).unwrap();
+        file.set_modified(SystemTime::now() - age).unwrap();
+        path
+    }
+
+    const HOUR: Duration = Duration::from_secs(60 * 60);
+
+    #[test]
+    fn test_finds_unnamed_screenshots_oldest_first() {
+        let dir = setup("catch_up_order");
+        let newest = touch(&dir, "Screenshot 2025-06-12 at 10.22.33.png", HOUR);
+        let oldest = touch(&dir, "Screenshot 2025-06-10 at 09.00.00.png", 72 * HOUR);
+        let middle = touch(&dir, "Screenshot_20250611_080000.png", 5 * HOUR);
+        touch(&dir, "login-page.png", HOUR);
+        touch(&dir, ".Screenshot 2025-06-12 at 10.22.34.png", HOUR);
+        fs::create_dir(dir.join("Screenshot 2025-06-12 at 11.00.00.png")).unwrap();
+
+        let found = find_unnamed_screenshots(
+            std::slice::from_ref(&dir),
+            &ScreenshotMatcher::default(),
+            None,
+        );
+        assert_eq!(found, vec![oldest, middle, newest]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_max_age_cutoff() {
+        let dir = setup("catch_up_max_age");
+        let recent = touch(&dir, "Screenshot 2025-06-12 at 10.22.33.png", HOUR);
+        touch(&dir, "Screenshot 2025-06-10 at 09.00.00.png", 72 * HOUR);
+
+        let found = find_unnamed_screenshots(
+            std::slice::from_ref(&dir),
+            &ScreenshotMatcher::default(),
+            Some(24 * HOUR),
+        );
+        assert_eq!(found, vec![recent]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_uses_the_given_matcher_across_folders() {
+        let desktop = setup("catch_up_matcher_desktop");
+        let captures = setup("catch_up_matcher_captures");
+        touch(&desktop, "Screenshot 2025-06-12 at 10.22.33.png", HOUR);
+        let snip = touch(&captures, "Snip_0001.PNG", 2 * HOUR);
+        touch(&captures, "Snip_0002.txt", HOUR);
+
+        let matcher = DetectionRules {
+            presets: Vec::new(),
+            patterns: vec![UserPattern::Glob("snip_*".to_string())],
+            extensions: vec!["png".to_string()],
+        }
+        .compile()
+        .unwrap();
+        // A folder that can't be read is skipped, not fatal
+        let dirs = [
+            desktop.clone(),
+            captures.clone(),
+            PathBuf::from("tests/assets/catch_up_missing"),
+        ];
+        assert_eq!(find_unnamed_screenshots(&dirs, &matcher, None), vec![snip]);
+
+        fs::remove_dir_all(&desktop).unwrap();
+        fs::remove_dir_all(&captures).unwrap();
+    }
+}
@@ -0,0 +1,299 @@
+use globset::{GlobBuilder, GlobMatcher};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tauri::AppHandle;
+
+use crate::store::{data_file, load_json, save_json};
+
+const RULES_FILE: &str = "detection_rules.json";
+
+/// Built-in filename conventions of common screenshot tools.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Preset {
+    /// "Screenshot 2025-06-12 at 10.22.33.png", or "Screen Shot …" before Ventura
+    MacosEnglish,
+    /// "Bildschirmfoto 2025-06-12 um 10.22.33.png"
+    MacosGerman,
+    /// "Capture d’écran 2025-06-12 à 10.22.33.png"
+    MacosFrench,
+    /// "Captura de pantalla 2025-06-12 a las 10.22.33.png"
+    MacosSpanish,
+    /// "スクリーンショット 2025-06-12 10.22.33.png"
+    MacosJapanese,
+    /// "Screenshot from 2025-06-12 10-22-33.png"
+    Gnome,
+    /// "Screenshot_20250612_102233.png"
+    KdeSpectacle,
+    /// "2025-06-12_10-22.png"
+    Flameshot,
+    /// "Screenshot 2025-06-12 102233.png"
+    WindowsSnippingTool,
+    /// "Screenshot (12).png" from Win+PrtScn
+    WindowsPrintScreen,
+    /// Any name containing "screenshot", which is all the app used to accept
+    Generic,
+}
+
+impl Preset {
+    pub const ALL: [Preset; 11] = [
+        Preset::MacosEnglish,
+        Preset::MacosGerman,
+        Preset::MacosFrench,
+        Preset::MacosSpanish,
+        Preset::MacosJapanese,
+        Preset::Gnome,
+        Preset::KdeSpectacle,
+        Preset::Flameshot,
+        Preset::WindowsSnippingTool,
+        Preset::WindowsPrintScreen,
+        Preset::Generic,
+    ];
+
+    /// Regex matched against the full file name. Accented letters accept both
+    /// composed and decomposed forms because HFS+ stores names decomposed.
+    fn pattern(&self) -> &'static str {
+        match self {
+            Preset::MacosEnglish => r"^(Screenshot|Screen Shot) \d{4}-\d{2}-\d{2} at ",
+            Preset::MacosGerman => r"^Bildschirmfoto \d{4}-\d{2}-\d{2} um ",
+            Preset::MacosFrench => {
+                r"^Capture d['’](é|e\x{301})cran \d{4}-\d{2}-\d{2} (à|a\x{300}) "
+            }
+            Preset::MacosSpanish => r"^Captura de pantalla \d{4}-\d{2}-\d{2} a las ",
+            Preset::MacosJapanese => r"^スクリーンショット \d{4}-\d{2}-\d{2} ",
+            Preset::Gnome => r"^Screenshot from \d{4}-\d{2}-\d{2} \d{2}-\d{2}-\d{2}",
+            Preset::KdeSpectacle => r"^Screenshot_\d{8}_\d{6}",
+            Preset::Flameshot => r"^\d{4}-\d{2}-\d{2}_\d{2}-\d{2}(-\d{2})?(_\d+)?\.[^.]+$",
+            Preset::WindowsSnippingTool => r"^Screenshot \d{4}-\d{2}-\d{2} \d{6}\.[^.]+$",
+            Preset::WindowsPrintScreen => r"^Screenshot( \(\d+\))?\.[^.]+$",
+            Preset::Generic => r"(?i)screenshot",
+        }
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        match self {
+            Preset::MacosEnglish
+            | Preset::MacosGerman
+            | Preset::MacosFrench
+            | Preset::MacosSpanish
+            | Preset::MacosJapanese => &["png", "jpg", "jpeg", "heic", "tiff", "gif"],
+            Preset::Gnome | Preset::WindowsSnippingTool | Preset::WindowsPrintScreen => &["png"],
+            Preset::KdeSpectacle => &["png", "jpg", "jpeg", "webp"],
+            Preset::Flameshot => &["png", "jpg", "jpeg"],
+            Preset::Generic => &["png"],
+        }
+    }
+}
+
+/// A user-defined name pattern, matched against the full file name.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "pattern", rename_all = "snake_case")]
+pub enum UserPattern {
+    /// Case-insensitive shell glob, e.g. `Snip_*`
+    Glob(String),
+    /// Regular expression, e.g. `^capture-\d+`
+    Regex(String),
+}
+
+/// Which files count as screenshots, as saved in `detection_rules.json`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DetectionRules {
+    pub presets: Vec<Preset>,
+    pub patterns: Vec<UserPattern>,
+    /// Extensions accepted for files matched by `patterns`
+    pub extensions: Vec<String>,
+}
+
+impl Default for DetectionRules {
+    fn default() -> Self {
+        DetectionRules {
+            presets: Preset::ALL.to_vec(),
+            patterns: Vec::new(),
+            extensions: vec!["png".into(), "jpg".into(), "jpeg".into()],
+        }
+    }
+}
+
+enum NameMatcher {
+    Regex(Regex),
+    Glob(GlobMatcher),
+}
+
+impl NameMatcher {
+    fn is_match(&self, file_name: &str) -> bool {
+        match self {
+            NameMatcher::Regex(re) => re.is_match(file_name),
+            NameMatcher::Glob(glob) => glob.is_match(file_name),
+        }
+    }
+}
+
+struct CompiledRule {
+    name: NameMatcher,
+    extensions: Vec<String>,
+}
+
+/// Compiled form of [`DetectionRules`] used by the watcher.
+pub struct ScreenshotMatcher {
+    rules: Vec<CompiledRule>,
+}
+
+impl Default for ScreenshotMatcher {
+    fn default() -> Self {
+        DetectionRules::default()
+            .compile()
+            .expect("built-in presets must compile")
+    }
+}
+
+impl DetectionRules {
+    pub fn compile(&self) -> Result<ScreenshotMatcher, String> {
+        let mut rules = Vec::new();
+
+        for preset in &self.presets {
+            let re = Regex::new(preset.pattern())
+                .map_err(|e| format!("Preset {:?} failed to compile: {}", preset, e))?;
+            rules.push(CompiledRule {
+                name: NameMatcher::Regex(re),
+                extensions: preset.extensions().iter().map(|e| e.to_string()).collect(),
+            });
+        }
+
+        let extensions: Vec<String> = self
+            .extensions
+            .iter()
+            .map(|e| e.trim().trim_start_matches('.').to_lowercase())
+            .filter(|e| !e.is_empty())
+            .collect();
+
+        for pattern in &self.patterns {
+            let name = match pattern {
+                UserPattern::Glob(glob) => {
+                    let glob = GlobBuilder::new(glob)
+                        .case_insensitive(true)
+                        .build()
+                        .map_err(|e| format!("Invalid glob '{}': {}", glob, e))?;
+                    NameMatcher::Glob(glob.compile_matcher())
+                }
+                UserPattern::Regex(re) => NameMatcher::Regex(
+                    Regex::new(re).map_err(|e| format!("Invalid regex '{}': {}", re, e))?,
+                ),
+            };
+            rules.push(CompiledRule {
+                name,
+                extensions: extensions.clone(),
+            });
+        }
+
+        Ok(ScreenshotMatcher { rules })
+    }
+}
+
+impl ScreenshotMatcher {
+    /// Checks the file name only; the caller decides whether the path exists.
+    pub fn matches(&self, path: &Path) -> bool {
+        let Some(file_name) = path.file_name().map(|n| n.to_string_lossy()) else {
+            return false;
+        };
+        // macOS writes a hidden ".Screenshot …" temp file before the real one
+        if file_name.starts_with('.') {
+            return false;
+        }
+        let Some(ext) = path.extension().map(|e| e.to_string_lossy().to_lowercase()) else {
+            return false;
+        };
+
+        self.rules
+            .iter()
+            .any(|rule| rule.extensions.contains(&ext) && rule.name.is_match(&file_name))
+    }
+}
+
+pub fn load_detection_rules(app_handle: &AppHandle) -> DetectionRules {
+    match data_file(app_handle, RULES_FILE) {
+        Some(path) => load_json(&path),
+        None => DetectionRules::default(),
+    }
+}
+
+pub fn save_detection_rules(app_handle: &AppHandle, rules: &DetectionRules) {
+    let Some(path) = data_file(app_handle, RULES_FILE) else {
+        return;
+    };
+    if let Err(e) = save_json(&path, rules) {
+        eprintln!("❌ Failed to save detection rules: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn test_builtin_presets() {
+        let matcher = ScreenshotMatcher::default();
+
+        let screenshots = [
+            "Screenshot 2025-06-12 at 10.22.33.png",
+            "Screen Shot 2020-01-01 at 1.02.03 PM.jpg",
+            "Bildschirmfoto 2025-06-12 um 10.22.33.png",
+            "Capture d’écran 2025-06-12 à 10.22.33.png",
+            "Capture d\u{2019}e\u{301}cran 2025-06-12 a\u{300} 10.22.33.png", // decomposed, as HFS+ stores it
+            "スクリーンショット 2025-06-12 10.22.33.png",
+            "Screenshot from 2025-06-12 10-22-33.png",
+            "Screenshot_20250612_102233.webp",
+            "2025-06-12_10-22.png",
+            "Screenshot 2025-06-12 102233.png",
+            "Screenshot (12).png",
+        ];
+        for name in screenshots {
+            assert!(matcher.matches(Path::new(name)), "Should detect {}", name);
+        }
+
+        let not_screenshots = [
+            ".Screenshot 2025-06-12 at 10.22.33.png",
+            "image_01.png",
+            "screenshot.txt",
+            "login-page.png",
+        ];
+        for name in not_screenshots {
+            assert!(!matcher.matches(Path::new(name)), "Should skip {}", name);
+        }
+    }
+
+    #[test]
+    fn test_user_patterns() {
+        let rules = DetectionRules {
+            presets: vec![],
+            patterns: vec![
+                UserPattern::Glob("snip_*".to_string()),
+                UserPattern::Regex(r"^cap-\d+".to_string()),
+            ],
+            extensions: vec![".PNG".to_string()],
+        };
+        let matcher = rules.compile().expect("Rules should compile");
+
+        assert!(
+            matcher.matches(Path::new("Snip_1.png")),
+            "Globs are case-insensitive"
+        );
+        assert!(matcher.matches(Path::new("cap-12.PNG")));
+        assert!(
+            !matcher.matches(Path::new("cap-12.jpg")),
+            "Extension not in the list"
+        );
+
+        let invalid = DetectionRules {
+            presets: vec![],
+            patterns: vec![UserPattern::Regex("(".to_string())],
+            extensions: vec![],
+        };
+        assert!(
+            invalid.compile().is_err(),
+            "Invalid regex should be reported"
+        );
+    }
+}
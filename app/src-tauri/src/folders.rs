@@ -0,0 +1,264 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+use crate::store::{data_file, load_json, save_json};
+
+const FOLDERS_FILE: &str = "watched_folders.json";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WatchedFolder {
+    pub path: String,
+    pub enabled: bool,
+    #[serde(default)]
+    pub mode: FolderMode,
+}
+
+/// What happens once a screenshot in the folder has been named.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FolderMode {
+    /// Rename right away
+    #[default]
+    Auto,
+    /// Put the suggestion in the review inbox instead
+    Review,
+}
+
+/// Loads the saved folder list. On first launch the Desktop is watched, which
+/// matches what the app did before folders were configurable. A saved list
+/// that is empty stays empty.
+pub fn load_watched_folders(app_handle: &AppHandle) -> Vec<WatchedFolder> {
+    if let Some(folders) = data_file(app_handle, FOLDERS_FILE).and_then(|path| read_folders(&path))
+    {
+        return folders;
+    }
+
+    match app_handle.path().desktop_dir() {
+        Ok(desktop) => vec![WatchedFolder {
+            path: desktop.to_string_lossy().to_string(),
+            enabled: true,
+            mode: FolderMode::Auto,
+        }],
+        Err(e) => {
+            eprintln!("❌ Failed to get desktop directory: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// The folders saved in `path`, or `None` if the list was never saved.
+fn read_folders(path: &Path) -> Option<Vec<WatchedFolder>> {
+    path.exists().then(|| load_json(path))
+}
+
+pub fn save_watched_folders(app_handle: &AppHandle, folders: &[WatchedFolder]) {
+    let Some(path) = data_file(app_handle, FOLDERS_FILE) else {
+        return;
+    };
+    if let Err(e) = save_json(&path, &folders) {
+        eprintln!("❌ Failed to save watched folders: {}", e);
+    }
+}
+
+/// Canonical form used to compare folders, so `~/Desktop` and `~/Desktop/`
+/// are treated as the same entry.
+pub fn normalize_folder_path(path: &str) -> Result<String, String> {
+    let path = Path::new(path.trim());
+    if !path.is_dir() {
+        return Err(format!("Not a directory: {}", path.display()));
+    }
+    let canonical: PathBuf = path
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve {}: {}", path.display(), e))?;
+    Ok(canonical.to_string_lossy().to_string())
+}
+
+pub fn add_folder(folders: &mut Vec<WatchedFolder>, path: &str) -> Result<(), String> {
+    let path = normalize_folder_path(path)?;
+    if folders.iter().any(|f| f.path == path) {
+        return Err(format!("Already watching {}", path));
+    }
+    folders.push(WatchedFolder {
+        path,
+        enabled: true,
+        mode: FolderMode::Auto,
+    });
+    Ok(())
+}
+
+/// Index of the entry for `path`, which may be spelled differently from the
+/// canonical path that is stored.
+fn find_folder(folders: &[WatchedFolder], path: &str) -> Result<usize, String> {
+    folders
+        .iter()
+        .position(|f| f.path == path)
+        .or_else(|| {
+            let path = normalize_folder_path(path).ok()?;
+            folders.iter().position(|f| f.path == path)
+        })
+        .ok_or_else(|| format!("Not watching {}", path))
+}
+
+pub fn remove_folder(
+    folders: &mut Vec<WatchedFolder>,
+    path: &str,
+) -> Result<WatchedFolder, String> {
+    let index = find_folder(folders, path)?;
+    Ok(folders.remove(index))
+}
+
+/// Returns the updated entry, whose canonical path is what the watcher uses.
+pub fn set_folder_enabled<'a>(
+    folders: &'a mut [WatchedFolder],
+    path: &str,
+    enabled: bool,
+) -> Result<&'a WatchedFolder, String> {
+    let folder = &mut folders[find_folder(folders, path)?];
+    folder.enabled = enabled;
+    Ok(folder)
+}
+
+pub fn set_folder_mode(
+    folders: &mut [WatchedFolder],
+    path: &str,
+    mode: FolderMode,
+) -> Result<(), String> {
+    let index = find_folder(folders, path)?;
+    folders[index].mode = mode;
+    Ok(())
+}
+
+/// Mode of the innermost watched folder containing `path`.
+pub fn folder_mode(folders: &[WatchedFolder], path: &Path) -> FolderMode {
+    folders
+        .iter()
+        .filter(|f| path.starts_with(&f.path))
+        .max_by_key(|f| f.path.len())
+        .map(|f| f.mode)
+        .unwrap_or_default()
+}
+
+/// Folders the watcher should currently have a `notify` watch on.
+pub fn enabled_paths(folders: &[WatchedFolder]) -> Vec<PathBuf> {
+    folders
+        .iter()
+        .filter(|f| f.enabled)
+        .map(|f| PathBuf::from(&f.path))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn setup(dir: &str) -> PathBuf {
+        let dir = PathBuf::from("tests/assets").join(dir);
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn canonical(path: &Path) -> String {
+        path.canonicalize().unwrap().to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn test_add_folder_stores_canonical_path_once() {
+        let dir = setup("folders_add");
+        let mut folders = Vec::new();
+
+        add_folder(&mut folders, &format!(" {} ", dir.display())).unwrap();
+        assert_eq!(folders.len(), 1);
+        assert_eq!(folders[0].path, canonical(&dir));
+        assert!(folders[0].enabled);
+        assert_eq!(folders[0].mode, FolderMode::Auto);
+
+        // Other spellings of the same folder are duplicates
+        for same in [
+            format!("{}/", dir.display()),
+            format!("{}/../folders_add", dir.display()),
+            canonical(&dir),
+        ] {
+            assert!(add_folder(&mut folders, &same).is_err(), "{}", same);
+        }
+        assert_eq!(folders.len(), 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_add_folder_rejects_missing_and_non_directory() {
+        let dir = setup("folders_reject");
+        let file = dir.join("Screenshot 1.png");
+        fs::write(&file, b"png").unwrap();
+        let mut folders = Vec::new();
+
+        assert!(add_folder(&mut folders, &dir.join("missing").to_string_lossy()).is_err());
+        assert!(add_folder(&mut folders, &file.to_string_lossy()).is_err());
+        assert!(normalize_folder_path(&file.to_string_lossy()).is_err());
+        assert!(folders.is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_remove_folder() {
+        let dir = setup("folders_remove");
+        let mut folders = Vec::new();
+        add_folder(&mut folders, &dir.to_string_lossy()).unwrap();
+
+        assert!(remove_folder(&mut folders, "/not/watched").is_err());
+        assert_eq!(folders.len(), 1);
+
+        // Found under another spelling too
+        let removed = remove_folder(&mut folders, &format!("{}/", dir.display())).unwrap();
+        assert_eq!(removed.path, canonical(&dir));
+        assert!(folders.is_empty());
+        assert!(remove_folder(&mut folders, &canonical(&dir)).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_enabled_paths_follow_set_folder_enabled() {
+        let dir = setup("folders_enabled");
+        let (first, second) = (dir.join("first"), dir.join("second"));
+        fs::create_dir_all(&first).unwrap();
+        fs::create_dir_all(&second).unwrap();
+        let mut folders = Vec::new();
+        add_folder(&mut folders, &first.to_string_lossy()).unwrap();
+        add_folder(&mut folders, &second.to_string_lossy()).unwrap();
+
+        let updated =
+            set_folder_enabled(&mut folders, &format!("{}/", first.display()), false).unwrap();
+        assert_eq!(updated.path, canonical(&first));
+        assert!(!updated.enabled);
+        assert_eq!(
+            enabled_paths(&folders),
+            vec![PathBuf::from(canonical(&second))]
+        );
+
+        set_folder_enabled(&mut folders, &canonical(&first), true).unwrap();
+        assert_eq!(enabled_paths(&folders).len(), 2);
+
+        assert!(set_folder_enabled(&mut folders, "/not/watched", false).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_saved_empty_list_stays_empty() {
+        let dir = setup("folders_saved");
+        let file = dir.join(FOLDERS_FILE);
+
+        // Never saved, so the caller falls back to the Desktop
+        assert_eq!(read_folders(&file), None);
+
+        save_json(&file, &Vec::<WatchedFolder>::new()).unwrap();
+        assert_eq!(read_folders(&file), Some(Vec::new()));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
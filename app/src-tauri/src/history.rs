@@ -0,0 +1,413 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+use tauri::AppHandle;
+
+use crate::{
+    ledger::hash_file,
+    rename::{rename_no_clobber, ConflictStrategy},
+    store::{data_file, load_json, save_json},
+};
+
+const HISTORY_FILE: &str = "rename_history.json";
+
+/// Oldest entries are dropped past this so the file stays small.
+const MAX_ENTRIES: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RenameStatus {
+    Renamed,
+    Undone,
+}
+
+/// One rename the app made.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryEntry {
+    pub id: u64,
+    pub original_path: PathBuf,
+    pub new_path: PathBuf,
+    /// SHA-256 of the file when it was renamed, to tell it from a different
+    /// file put at `new_path` since
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_hash: Option<String>,
+    pub name: String,
+    pub renamed_at: DateTime<Utc>,
+    pub status: RenameStatus,
+    pub undone_at: Option<DateTime<Utc>>,
+}
+
+/// What `undo_last` put back, and what it couldn't.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UndoReport {
+    pub undone: Vec<HistoryEntry>,
+    pub failed: Vec<UndoFailure>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UndoFailure {
+    pub id: u64,
+    pub error: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HistoryData {
+    next_id: u64,
+    entries: Vec<HistoryEntry>,
+}
+
+/// Every rename, saved as `rename_history.json` in the app data dir so a bad
+/// name can be found and put back later.
+#[derive(Clone)]
+pub struct RenameHistory {
+    data: Arc<Mutex<HistoryData>>,
+    file: Option<PathBuf>,
+}
+
+impl RenameHistory {
+    pub fn load(app_handle: &AppHandle) -> Self {
+        Self::open(data_file(app_handle, HISTORY_FILE))
+    }
+
+    /// Reads the history from `file`; `None` keeps it in memory only.
+    pub fn open(file: Option<PathBuf>) -> Self {
+        let data = match &file {
+            Some(path) => load_json(path),
+            None => HistoryData::default(),
+        };
+        RenameHistory {
+            data: Arc::new(Mutex::new(data)),
+            file,
+        }
+    }
+
+    /// Newest first, at most `limit` entries.
+    pub fn recent(&self, limit: Option<usize>) -> Vec<HistoryEntry> {
+        let data = self.data.lock().unwrap();
+        data.entries
+            .iter()
+            .rev()
+            .take(limit.unwrap_or(usize::MAX))
+            .cloned()
+            .collect()
+    }
+
+    pub fn record(
+        &self,
+        original_path: &Path,
+        new_path: &Path,
+        name: &str,
+        content_hash: Option<String>,
+    ) -> HistoryEntry {
+        let mut data = self.data.lock().unwrap();
+        data.next_id += 1;
+        let entry = HistoryEntry {
+            id: data.next_id,
+            original_path: original_path.to_path_buf(),
+            new_path: new_path.to_path_buf(),
+            content_hash,
+            name: name.to_string(),
+            renamed_at: Utc::now(),
+            status: RenameStatus::Renamed,
+            undone_at: None,
+        };
+        data.entries.push(entry.clone());
+        let overflow = data.entries.len().saturating_sub(MAX_ENTRIES);
+        data.entries.drain(..overflow);
+        self.save(&data);
+        entry
+    }
+
+    /// Moves the file back to its original path. Fails rather than replace a
+    /// file that now has the old name, or when the renamed file has since been
+    /// moved, deleted or replaced by another file.
+    pub fn undo(&self, id: u64) -> Result<HistoryEntry, String> {
+        // Held throughout so two undos of the same entry can't race
+        let mut data = self.data.lock().unwrap();
+        let entry = data
+            .entries
+            .iter_mut()
+            .find(|entry| entry.id == id)
+            .ok_or_else(|| format!("No rename with id {}", id))?;
+
+        if entry.status == RenameStatus::Undone {
+            return Err(format!("'{}' was already undone", entry.name));
+        }
+        if !entry.new_path.is_file() {
+            return Err(format!(
+                "{} was moved or deleted since it was renamed",
+                entry.new_path.display()
+            ));
+        }
+        if let Some(expected) = &entry.content_hash {
+            let hash = hash_file(&entry.new_path)
+                .map_err(|e| format!("Failed to read {}: {}", entry.new_path.display(), e))?;
+            if hash != *expected {
+                return Err(format!(
+                    "{} is no longer the file that was renamed",
+                    entry.new_path.display()
+                ));
+            }
+        }
+
+        let original = &entry.original_path;
+        let dir = original
+            .parent()
+            .ok_or("Can't determine original directory")?;
+        let stem = original
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let ext = original
+            .extension()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        match rename_no_clobber(&entry.new_path, dir, &stem, &ext, ConflictStrategy::Skip) {
+            Ok(Some(_)) => {}
+            Ok(None) => {
+                return Err(format!(
+                    "{} already exists, not replacing it",
+                    original.display()
+                ))
+            }
+            Err(e) => {
+                return Err(format!(
+                    "Failed to move {} back: {}",
+                    entry.new_path.display(),
+                    e
+                ))
+            }
+        }
+
+        entry.status = RenameStatus::Undone;
+        entry.undone_at = Some(Utc::now());
+        let entry = entry.clone();
+        self.save(&data);
+        Ok(entry)
+    }
+
+    /// Undoes the `n` most recent renames that are still in place, newest
+    /// first. One that can't be undone is reported and the rest are still
+    /// tried.
+    pub fn undo_last(&self, n: usize) -> UndoReport {
+        let ids: Vec<u64> = {
+            let data = self.data.lock().unwrap();
+            data.entries
+                .iter()
+                .rev()
+                .filter(|entry| entry.status == RenameStatus::Renamed)
+                .take(n)
+                .map(|entry| entry.id)
+                .collect()
+        };
+
+        let mut report = UndoReport::default();
+        for id in ids {
+            match self.undo(id) {
+                Ok(entry) => report.undone.push(entry),
+                Err(error) => report.failed.push(UndoFailure { id, error }),
+            }
+        }
+        report
+    }
+
+    fn save(&self, data: &HistoryData) {
+        if let Some(file) = &self.file {
+            if let Err(e) = save_json(file, data) {
+                eprintln!("❌ Failed to save rename history: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    fn setup(dir: &str) -> PathBuf {
+        let dir = PathBuf::from("tests/assets").join(dir);
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Renames `from` to `to` the way the app does and records it.
+    fn rename(history: &RenameHistory, from: &Path, to: &Path) -> u64 {
+        fs::write(from, b"capture").unwrap();
+        fs::rename(from, to).unwrap();
+        history.record(from, to, "renamed", hash_file(to).ok()).id
+    }
+
+    #[test]
+    fn test_undo_restores_original_name() {
+        let dir = setup("history_undo");
+        let original = dir.join("Screenshot 1.png");
+        let renamed = dir.join("login-page.png");
+        let history = RenameHistory::open(None);
+        let id = rename(&history, &original, &renamed);
+
+        let entry = history.undo(id).unwrap();
+        assert_eq!(entry.status, RenameStatus::Undone);
+        assert!(original.exists());
+        assert!(!renamed.exists());
+
+        // Already undone
+        assert!(history.undo(id).is_err());
+        assert_eq!(history.recent(None)[0].status, RenameStatus::Undone);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_undo_never_replaces_a_file() {
+        let dir = setup("history_conflict");
+        let original = dir.join("Screenshot 1.png");
+        let renamed = dir.join("login-page.png");
+        let history = RenameHistory::open(None);
+        let id = rename(&history, &original, &renamed);
+
+        // A new screenshot took the old name in the meantime
+        fs::write(&original, b"newer capture").unwrap();
+        assert!(history.undo(id).is_err());
+        assert_eq!(fs::read(&original).unwrap(), b"newer capture");
+        assert!(renamed.exists());
+        assert_eq!(history.recent(None)[0].status, RenameStatus::Renamed);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_undo_detects_moved_file() {
+        let dir = setup("history_moved");
+        let original = dir.join("Screenshot 1.png");
+        let renamed = dir.join("login-page.png");
+        let history = RenameHistory::open(None);
+        let id = rename(&history, &original, &renamed);
+
+        fs::rename(&renamed, dir.join("elsewhere.png")).unwrap();
+        let err = history.undo(id).unwrap_err();
+        assert!(err.contains("moved or deleted"), "{}", err);
+        assert!(!original.exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_undo_detects_replaced_file() {
+        let dir = setup("history_replaced");
+        let original = dir.join("Screenshot 1.png");
+        let renamed = dir.join("login-page.png");
+        let history = RenameHistory::open(None);
+        let id = rename(&history, &original, &renamed);
+
+        // Moved away, and another file given the same name
+        fs::rename(&renamed, dir.join("elsewhere.png")).unwrap();
+        fs::write(&renamed, b"another capture").unwrap();
+        let err = history.undo(id).unwrap_err();
+        assert!(err.contains("no longer the file"), "{}", err);
+        assert!(!original.exists());
+        assert_eq!(fs::read(&renamed).unwrap(), b"another capture");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_undo_last_n_newest_first() {
+        let dir = setup("history_last_n");
+        let history = RenameHistory::open(None);
+        for i in 1..=3 {
+            rename(
+                &history,
+                &dir.join(format!("Screenshot {}.png", i)),
+                &dir.join(format!("named-{}.png", i)),
+            );
+        }
+
+        let report = history.undo_last(2);
+        assert!(report.failed.is_empty());
+        let names: Vec<_> = report.undone.iter().map(|e| e.new_path.clone()).collect();
+        assert_eq!(
+            names,
+            vec![dir.join("named-3.png"), dir.join("named-2.png")]
+        );
+        assert!(dir.join("named-1.png").exists());
+        assert!(dir.join("Screenshot 2.png").exists());
+        assert!(dir.join("Screenshot 3.png").exists());
+
+        // Only the one still in place is left to undo
+        assert_eq!(history.undo_last(5).undone.len(), 1);
+        assert!(dir.join("Screenshot 1.png").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_undo_last_n_keeps_going_past_failures() {
+        let dir = setup("history_last_n_failure");
+        let history = RenameHistory::open(None);
+        let ids: Vec<u64> = (1..=3)
+            .map(|i| {
+                rename(
+                    &history,
+                    &dir.join(format!("Screenshot {}.png", i)),
+                    &dir.join(format!("named-{}.png", i)),
+                )
+            })
+            .collect();
+
+        // A new screenshot took the middle one's old name
+        fs::write(dir.join("Screenshot 2.png"), b"newer capture").unwrap();
+
+        let report = history.undo_last(3);
+        let undone: Vec<u64> = report.undone.iter().map(|e| e.id).collect();
+        assert_eq!(undone, vec![ids[2], ids[0]]);
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].id, ids[1]);
+        assert!(report.failed[0].error.contains("already exists"));
+
+        assert!(dir.join("Screenshot 1.png").exists());
+        assert!(dir.join("Screenshot 3.png").exists());
+        assert!(dir.join("named-2.png").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_history_survives_reopen() {
+        let file = PathBuf::from("tests/assets/history_reopen.json");
+        fs::create_dir_all("tests/assets").unwrap();
+        let _ = fs::remove_file(&file);
+
+        let history = RenameHistory::open(Some(file.clone()));
+        history.record(
+            Path::new("/a/Screenshot.png"),
+            Path::new("/a/first.png"),
+            "first",
+            None,
+        );
+        history.record(
+            Path::new("/a/Screenshot 2.png"),
+            Path::new("/a/second.png"),
+            "second",
+            None,
+        );
+        drop(history);
+
+        let reopened = RenameHistory::open(Some(file.clone()));
+        let recent = reopened.recent(Some(1));
+        assert_eq!(recent.len(), 1);
+        assert_eq!(recent[0].name, "second");
+
+        fs::remove_file(&file).unwrap();
+    }
+}
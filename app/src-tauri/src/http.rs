@@ -0,0 +1,32 @@
+use reqwest::blocking::Client;
+use std::{sync::OnceLock, time::Duration};
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Whole request, upload and answer included, so a hung server can't hold a
+/// worker forever. Vision models can take a while before the first byte.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
+
+const POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+const TCP_KEEPALIVE: Duration = Duration::from_secs(60);
+
+/// The one HTTP client the app uses, so connections to the naming and
+/// registration servers are kept alive and reused between screenshots.
+/// Requests block the calling thread, so this is only used from worker
+/// threads and setup, never from async commands.
+pub fn client() -> &'static Client {
+    static CLIENT: OnceLock<Client> = OnceLock::new();
+    CLIENT.get_or_init(|| {
+        Client::builder()
+            .connect_timeout(CONNECT_TIMEOUT)
+            .timeout(REQUEST_TIMEOUT)
+            .pool_idle_timeout(POOL_IDLE_TIMEOUT)
+            .tcp_keepalive(TCP_KEEPALIVE)
+            .user_agent(concat!("SnapName/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .unwrap_or_else(|e| {
+                eprintln!("❌ Failed to build HTTP client, using defaults: {}", e);
+                Client::new()
+            })
+    })
+}
@@ -0,0 +1,187 @@
+use serde::Serialize;
+use std::{fs, io::Cursor, io::Read, path::Path};
+
+/// Image containers screenshot tools are known to write.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageFormat {
+    Png,
+    Jpeg,
+    Webp,
+    Gif,
+    Tiff,
+    Heic,
+}
+
+impl ImageFormat {
+    /// Sniffs the format from the first bytes of the file, ignoring its extension.
+    pub fn detect(header: &[u8]) -> Option<ImageFormat> {
+        if header.starts_with(&[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A]) {
+            return Some(ImageFormat::Png);
+        }
+        if header.starts_with(&[0xFF, 0xD8, 0xFF]) {
+            return Some(ImageFormat::Jpeg);
+        }
+        if header.starts_with(b"GIF87a") || header.starts_with(b"GIF89a") {
+            return Some(ImageFormat::Gif);
+        }
+        if header.len() >= 12 && &header[0..4] == b"RIFF" && &header[8..12] == b"WEBP" {
+            return Some(ImageFormat::Webp);
+        }
+        if header.starts_with(b"II*\0") || header.starts_with(b"MM\0*") {
+            return Some(ImageFormat::Tiff);
+        }
+        // ISO base media file: [size]["ftyp"][major brand]
+        if header.len() >= 12 && &header[4..8] == b"ftyp" {
+            let brand = &header[8..12];
+            let heif_brands: [&[u8]; 8] = [
+                b"heic", b"heix", b"hevc", b"hevx", b"heim", b"heis", b"mif1", b"msf1",
+            ];
+            if heif_brands.contains(&brand) {
+                return Some(ImageFormat::Heic);
+            }
+        }
+        None
+    }
+
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            ImageFormat::Png => "image/png",
+            ImageFormat::Jpeg => "image/jpeg",
+            ImageFormat::Webp => "image/webp",
+            ImageFormat::Gif => "image/gif",
+            ImageFormat::Tiff => "image/tiff",
+            ImageFormat::Heic => "image/heic",
+        }
+    }
+
+    /// Formats the naming backend accepts as-is. Everything else is converted
+    /// to PNG before upload.
+    pub fn is_accepted_by_backend(&self) -> bool {
+        matches!(
+            self,
+            ImageFormat::Png | ImageFormat::Jpeg | ImageFormat::Webp | ImageFormat::Gif
+        )
+    }
+}
+
+pub fn detect_file_format(path: &Path) -> Option<ImageFormat> {
+    let mut header = [0u8; 16];
+    let mut file = fs::File::open(path).ok()?;
+    let read = file.read(&mut header).ok()?;
+    ImageFormat::detect(&header[..read])
+}
+
+/// Re-encodes a screenshot the backend can't read as PNG.
+pub fn convert_to_png(path: &Path, format: ImageFormat) -> Result<Vec<u8>, String> {
+    match format {
+        ImageFormat::Heic => convert_heic_to_png(path),
+        _ => {
+            // Decode from the bytes, not the extension, which may be wrong
+            let bytes =
+                fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+            let img = image::load_from_memory(&bytes)
+                .map_err(|e| format!("Failed to decode {}: {}", path.display(), e))?;
+            let mut png = Vec::new();
+            img.write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)
+                .map_err(|e| format!("Failed to encode PNG: {}", e))?;
+            Ok(png)
+        }
+    }
+}
+
+/// No pure-Rust HEIC decoder is good enough yet, so lean on `sips`, which
+/// ships with every Mac and is what produced the HEIC in the first place.
+#[cfg(target_os = "macos")]
+fn convert_heic_to_png(path: &Path) -> Result<Vec<u8>, String> {
+    use std::sync::atomic::{AtomicU64, Ordering};
+    static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+    let out_path = std::env::temp_dir().join(format!(
+        "snapname-{}-{}.png",
+        std::process::id(),
+        NEXT_ID.fetch_add(1, Ordering::Relaxed)
+    ));
+
+    let output = std::process::Command::new("sips")
+        .args(["-s", "format", "png"])
+        .arg(path)
+        .arg("--out")
+        .arg(&out_path)
+        .output()
+        .map_err(|e| format!("Failed to run sips: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "sips failed to convert {}: {}",
+            path.display(),
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    let png = fs::read(&out_path).map_err(|e| format!("Failed to read converted PNG: {}", e));
+    let _ = fs::remove_file(&out_path);
+    png
+}
+
+#[cfg(not(target_os = "macos"))]
+fn convert_heic_to_png(path: &Path) -> Result<Vec<u8>, String> {
+    Err(format!(
+        "HEIC conversion is only supported on macOS: {}",
+        path.display()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_image_format() {
+        assert_eq!(
+            ImageFormat::detect(&[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A]),
+            Some(ImageFormat::Png)
+        );
+        assert_eq!(
+            ImageFormat::detect(&[0xFF, 0xD8, 0xFF, 0xE0]),
+            Some(ImageFormat::Jpeg)
+        );
+        assert_eq!(ImageFormat::detect(b"GIF89a"), Some(ImageFormat::Gif));
+        assert_eq!(
+            ImageFormat::detect(b"RIFF\0\0\0\0WEBPVP8 "),
+            Some(ImageFormat::Webp)
+        );
+        assert_eq!(ImageFormat::detect(b"II*\0"), Some(ImageFormat::Tiff));
+        assert_eq!(
+            ImageFormat::detect(b"\0\0\0\x18ftypheic\0\0"),
+            Some(ImageFormat::Heic)
+        );
+        assert_eq!(ImageFormat::detect(b"test image content"), None);
+
+        assert_eq!(ImageFormat::Jpeg.mime_type(), "image/jpeg");
+        assert!(!ImageFormat::Tiff.is_accepted_by_backend());
+    }
+
+    #[test]
+    fn test_convert_mislabeled_tiff_to_png() {
+        use std::io::Cursor;
+        use std::path::PathBuf;
+
+        // A TIFF saved with a .png extension should still be sniffed and converted
+        let path = PathBuf::from("tests/assets/Screenshot_tiff.png");
+        std::fs::create_dir_all("tests/assets").unwrap();
+
+        let mut tiff = Vec::new();
+        image::DynamicImage::new_rgb8(4, 4)
+            .write_to(&mut Cursor::new(&mut tiff), image::ImageFormat::Tiff)
+            .unwrap();
+        std::fs::write(&path, &tiff).unwrap();
+
+        assert_eq!(detect_file_format(&path), Some(ImageFormat::Tiff));
+        let png = convert_to_png(&path, ImageFormat::Tiff).expect("TIFF should convert");
+        assert_eq!(ImageFormat::detect(&png), Some(ImageFormat::Png));
+
+        // Clean up
+        std::fs::remove_file(&path).unwrap();
+    }
+}
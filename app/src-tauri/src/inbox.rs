@@ -0,0 +1,230 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+use tauri::AppHandle;
+
+use crate::store::{data_file, load_json, save_json};
+
+const INBOX_FILE: &str = "review_inbox.json";
+
+/// A name waiting for the user to accept, edit or reject it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingRename {
+    pub id: u64,
+    pub path: PathBuf,
+    /// Sanitized name without extension, as it would have been applied
+    pub suggested_name: String,
+    /// Folder a rule or the organize tree would move it to; `None` renames in place
+    pub target_dir: Option<PathBuf>,
+    /// Added by rules once the rename is accepted
+    pub tags: Vec<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct InboxData {
+    next_id: u64,
+    items: Vec<PendingRename>,
+}
+
+/// Suggestions from folders in review mode, saved as `review_inbox.json` in the
+/// app data dir so they survive a restart.
+#[derive(Clone)]
+pub struct ReviewInbox {
+    data: Arc<Mutex<InboxData>>,
+    file: Option<PathBuf>,
+}
+
+impl ReviewInbox {
+    pub fn load(app_handle: &AppHandle) -> Self {
+        Self::open(data_file(app_handle, INBOX_FILE))
+    }
+
+    /// Reads the inbox from `file`; `None` keeps it in memory only.
+    pub fn open(file: Option<PathBuf>) -> Self {
+        let data = match &file {
+            Some(path) => load_json(path),
+            None => InboxData::default(),
+        };
+        ReviewInbox {
+            data: Arc::new(Mutex::new(data)),
+            file,
+        }
+    }
+
+    pub fn items(&self) -> Vec<PendingRename> {
+        self.data.lock().unwrap().items.clone()
+    }
+
+    /// Adds a suggestion, replacing any older one for the same file.
+    pub fn add(
+        &self,
+        path: PathBuf,
+        suggested_name: String,
+        target_dir: Option<PathBuf>,
+        tags: Vec<String>,
+    ) -> PendingRename {
+        let mut data = self.data.lock().unwrap();
+        data.items.retain(|item| item.path != path);
+        data.next_id += 1;
+        let item = PendingRename {
+            id: data.next_id,
+            path,
+            suggested_name,
+            target_dir,
+            tags,
+            created_at: Utc::now(),
+        };
+        data.items.push(item.clone());
+        self.save(&data);
+        item
+    }
+
+    /// Whether `path` already has a suggestion waiting.
+    pub fn contains(&self, path: &Path) -> bool {
+        let data = self.data.lock().unwrap();
+        data.items.iter().any(|item| item.path == path)
+    }
+
+    pub fn get(&self, id: u64) -> Option<PendingRename> {
+        let data = self.data.lock().unwrap();
+        data.items.iter().find(|item| item.id == id).cloned()
+    }
+
+    /// Removes and returns the item with `id`.
+    pub fn take(&self, id: u64) -> Option<PendingRename> {
+        let mut data = self.data.lock().unwrap();
+        let index = data.items.iter().position(|item| item.id == id)?;
+        let item = data.items.remove(index);
+        self.save(&data);
+        Some(item)
+    }
+
+    // Saved under the lock so concurrent workers don't interleave writes
+    fn save(&self, data: &InboxData) {
+        if let Some(file) = &self.file {
+            if let Err(e) = save_json(file, data) {
+                eprintln!("❌ Failed to save review inbox: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::folders::{folder_mode, FolderMode, WatchedFolder};
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    fn folder(path: &str, mode: FolderMode) -> WatchedFolder {
+        WatchedFolder {
+            path: path.to_string(),
+            enabled: true,
+            mode,
+        }
+    }
+
+    #[test]
+    fn test_take_removes_item() {
+        let inbox = ReviewInbox::open(None);
+        let first = inbox.add(
+            PathBuf::from("/Users/me/Desktop/a.png"),
+            "login page".to_string(),
+            None,
+            vec![],
+        );
+        let second = inbox.add(
+            PathBuf::from("/Users/me/Desktop/b.png"),
+            "invoice".to_string(),
+            Some(PathBuf::from("/Users/me/Receipts")),
+            vec!["finance".to_string()],
+        );
+        assert_ne!(first.id, second.id);
+        assert_eq!(inbox.items().len(), 2);
+
+        assert!(inbox.contains(Path::new("/Users/me/Desktop/a.png")));
+
+        assert_eq!(inbox.take(first.id), Some(first.clone()));
+        assert_eq!(inbox.take(first.id), None);
+        assert!(!inbox.contains(Path::new("/Users/me/Desktop/a.png")));
+        assert_eq!(inbox.items(), vec![second]);
+    }
+
+    #[test]
+    fn test_newer_suggestion_replaces_older() {
+        let inbox = ReviewInbox::open(None);
+        let path = PathBuf::from("/Users/me/Desktop/a.png");
+        inbox.add(path.clone(), "old".to_string(), None, vec![]);
+        let newer = inbox.add(path, "new".to_string(), None, vec![]);
+        assert_eq!(inbox.items(), vec![newer]);
+    }
+
+    #[test]
+    fn test_inbox_survives_reopen() {
+        let file = PathBuf::from("tests/assets/inbox_reopen.json");
+        fs::create_dir_all("tests/assets").unwrap();
+        let _ = fs::remove_file(&file);
+
+        let inbox = ReviewInbox::open(Some(file.clone()));
+        let kept = inbox.add(
+            PathBuf::from("/Users/me/Desktop/a.png"),
+            "login page".to_string(),
+            None,
+            vec![],
+        );
+        let taken = inbox.add(
+            PathBuf::from("/Users/me/Desktop/b.png"),
+            "invoice".to_string(),
+            None,
+            vec![],
+        );
+        inbox.take(taken.id);
+        drop(inbox);
+
+        let reopened = ReviewInbox::open(Some(file.clone()));
+        assert_eq!(reopened.items(), vec![kept]);
+        // Ids keep counting up so an old id never points at a new item
+        let next = reopened.add(
+            PathBuf::from("/Users/me/Desktop/c.png"),
+            "c".to_string(),
+            None,
+            vec![],
+        );
+        assert!(next.id > taken.id);
+
+        fs::remove_file(&file).unwrap();
+    }
+
+    #[test]
+    fn test_folder_mode_uses_innermost_folder() {
+        let folders = vec![
+            folder("/Users/me/Desktop", FolderMode::Auto),
+            folder("/Users/me/Desktop/private", FolderMode::Review),
+        ];
+        assert_eq!(
+            folder_mode(&folders, Path::new("/Users/me/Desktop/shot.png")),
+            FolderMode::Auto
+        );
+        assert_eq!(
+            folder_mode(&folders, Path::new("/Users/me/Desktop/private/shot.png")),
+            FolderMode::Review
+        );
+        assert_eq!(
+            folder_mode(&folders, Path::new("/Users/me/Downloads/shot.png")),
+            FolderMode::Auto
+        );
+    }
+
+    #[test]
+    fn test_saved_folders_without_mode_auto_apply() {
+        let folder: WatchedFolder =
+            serde_json::from_str(r#"{"path": "/Users/me/Desktop", "enabled": true}"#).unwrap();
+        assert_eq!(folder.mode, FolderMode::Auto);
+    }
+}
@@ -0,0 +1,235 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+use tauri::AppHandle;
+
+use crate::store::{data_file, load_json, save_json};
+
+const LEDGER_FILE: &str = "processed_files.json";
+
+/// Oldest entries are dropped past this so the file stays small.
+const MAX_ENTRIES: usize = 5000;
+
+/// A screenshot that was already named.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LedgerEntry {
+    /// SHA-256 of the file contents, hex encoded
+    pub hash: String,
+    /// Where the file ended up after renaming
+    pub path: PathBuf,
+    /// Where it was found, if it moved, so undoing the rename doesn't get it
+    /// named again
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub original_path: Option<PathBuf>,
+    pub name: String,
+    pub processed_at: DateTime<Utc>,
+}
+
+impl LedgerEntry {
+    fn matches(&self, hash: &str, path: &Path) -> bool {
+        self.hash == hash && (self.path == path || self.original_path.as_deref() == Some(path))
+    }
+}
+
+/// Files already named, saved as `processed_files.json` in the app data dir so
+/// nothing is sent (and billed) twice, even across restarts.
+#[derive(Clone)]
+pub struct ProcessedLedger {
+    entries: Arc<Mutex<Vec<LedgerEntry>>>,
+    file: Option<PathBuf>,
+}
+
+impl ProcessedLedger {
+    pub fn load(app_handle: &AppHandle) -> Self {
+        Self::open(data_file(app_handle, LEDGER_FILE))
+    }
+
+    /// Reads the ledger from `file`; `None` keeps it in memory only.
+    pub fn open(file: Option<PathBuf>) -> Self {
+        let entries = match &file {
+            Some(path) => load_json(path),
+            None => Vec::new(),
+        };
+        ProcessedLedger {
+            entries: Arc::new(Mutex::new(entries)),
+            file,
+        }
+    }
+
+    pub fn contains(&self, hash: &str, path: &Path) -> bool {
+        self.entries
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|entry| entry.matches(hash, path))
+    }
+
+    /// Whether this exact file, at this path and with this content, was named
+    /// before. A file that can't be read is treated as new.
+    pub fn is_processed(&self, path: &Path) -> bool {
+        match hash_file(path) {
+            Ok(hash) => self.contains(&hash, path),
+            Err(_) => false,
+        }
+    }
+
+    /// Records a file named `name` that was found at `original_path` and now
+    /// lives at `path`, or is about to. A rename that fails must `forget` it
+    /// again, so the file is tried again.
+    pub fn record(&self, hash: String, original_path: &Path, path: &Path, name: &str) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|entry| !(entry.hash == hash && entry.path == path));
+        entries.push(LedgerEntry {
+            hash,
+            path: path.to_path_buf(),
+            original_path: (original_path != path).then(|| original_path.to_path_buf()),
+            name: name.to_string(),
+            processed_at: Utc::now(),
+        });
+        let overflow = entries.len().saturating_sub(MAX_ENTRIES);
+        entries.drain(..overflow);
+        self.save(&entries);
+    }
+
+    /// Drops the entry for `hash` at `path`, e.g. after a rename that was
+    /// recorded ahead of time didn't happen.
+    pub fn forget(&self, hash: &str, path: &Path) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|entry| !(entry.hash == hash && entry.path == path));
+        self.save(&entries);
+    }
+
+    // Saved under the lock so concurrent workers don't interleave writes
+    fn save(&self, entries: &[LedgerEntry]) {
+        if let Some(file) = &self.file {
+            if let Err(e) = save_json(file, &entries) {
+                eprintln!("❌ Failed to save processed-file ledger: {}", e);
+            }
+        }
+    }
+}
+
+pub fn hash_file(path: &Path) -> io::Result<String> {
+    let mut file = fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    fn cleanup(path: &Path) {
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_hash_file() {
+        let path = PathBuf::from("tests/assets/ledger_hash.png");
+        fs::create_dir_all("tests/assets").unwrap();
+        fs::write(&path, b"abc").unwrap();
+
+        assert_eq!(
+            hash_file(&path).unwrap(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert!(hash_file(Path::new("tests/assets/missing.png")).is_err());
+
+        cleanup(&path);
+    }
+
+    #[test]
+    fn test_ledger_matches_content_and_path() {
+        let path = PathBuf::from("tests/assets/ledger_match.png");
+        fs::create_dir_all("tests/assets").unwrap();
+        fs::write(&path, b"first capture").unwrap();
+
+        let ledger = ProcessedLedger::open(None);
+        assert!(!ledger.is_processed(&path));
+
+        ledger.record(hash_file(&path).unwrap(), &path, &path, "first-capture");
+        assert!(ledger.is_processed(&path));
+
+        // Same content somewhere else is a different file
+        let copy = PathBuf::from("tests/assets/ledger_match_copy.png");
+        fs::copy(&path, &copy).unwrap();
+        assert!(!ledger.is_processed(&copy));
+
+        // New content at the same path was never named
+        fs::write(&path, b"second capture").unwrap();
+        assert!(!ledger.is_processed(&path));
+
+        cleanup(&path);
+        cleanup(&copy);
+    }
+
+    #[test]
+    fn test_ledger_survives_reopen() {
+        let ledger_file = PathBuf::from("tests/assets/ledger_reopen.json");
+        let path = PathBuf::from("tests/assets/ledger_reopen.png");
+        fs::create_dir_all("tests/assets").unwrap();
+        cleanup(&ledger_file);
+        fs::write(&path, b"capture").unwrap();
+
+        let ledger = ProcessedLedger::open(Some(ledger_file.clone()));
+        ledger.record(hash_file(&path).unwrap(), &path, &path, "capture");
+        drop(ledger);
+
+        let reopened = ProcessedLedger::open(Some(ledger_file.clone()));
+        assert!(reopened.is_processed(&path));
+
+        cleanup(&path);
+        cleanup(&ledger_file);
+    }
+
+    #[test]
+    fn test_ledger_covers_renamed_file() {
+        let original = PathBuf::from("tests/assets/ledger_Screenshot 1.png");
+        let renamed = PathBuf::from("tests/assets/ledger_screenshot 2025-06-12.png");
+        fs::create_dir_all("tests/assets").unwrap();
+        fs::write(&original, b"capture").unwrap();
+        let hash = hash_file(&original).unwrap();
+        fs::rename(&original, &renamed).unwrap();
+
+        let ledger = ProcessedLedger::open(None);
+        ledger.record(hash, &original, &renamed, "screenshot 2025-06-12");
+        // A fallback name still looks like a screenshot, but isn't new
+        assert!(ledger.is_processed(&renamed));
+
+        // Nor is the file after the rename is undone
+        fs::rename(&renamed, &original).unwrap();
+        assert!(ledger.is_processed(&original));
+
+        cleanup(&original);
+    }
+
+    #[test]
+    fn test_forget_recorded_rename() {
+        let path = PathBuf::from("tests/assets/ledger_forget.png");
+        let expected = PathBuf::from("tests/assets/ledger_forget-renamed.png");
+        fs::create_dir_all("tests/assets").unwrap();
+        fs::write(&path, b"forget").unwrap();
+        let hash = hash_file(&path).unwrap();
+
+        // Recorded ahead of a rename that then fails
+        let ledger = ProcessedLedger::open(None);
+        ledger.record(hash.clone(), &path, &expected, "renamed");
+        assert!(ledger.contains(&hash, &expected));
+        assert!(ledger.is_processed(&path));
+
+        ledger.forget(&hash, &expected);
+        assert!(!ledger.contains(&hash, &expected));
+        assert!(!ledger.is_processed(&path));
+
+        cleanup(&path);
+    }
+}
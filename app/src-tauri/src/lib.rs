@@ -1,28 +1,75 @@
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
-use std::sync::{Arc, Mutex};
+use chrono::{DateTime, Local, Utc};
+use std::{
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
 use tauri::{
     image::Image,
     menu::{MenuBuilder, MenuItem},
     tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent},
-    App, AppHandle, Manager,
+    App, AppHandle, Emitter, Manager,
 };
 
 use crate::{
+    catch_up::CatchUpReport,
+    detect::{load_detection_rules, save_detection_rules, DetectionRules},
+    folders::{
+        add_folder, load_watched_folders, remove_folder, save_watched_folders, set_folder_enabled,
+        set_folder_mode, FolderMode, WatchedFolder,
+    },
+    history::{HistoryEntry, RenameHistory, UndoReport},
+    inbox::{PendingRename, ReviewInbox},
+    ledger::ProcessedLedger,
+    naming::DEFAULT_SYSTEM_PROMPT,
+    offline_queue::{retry_offline, spawn_offline_retry, OfflineQueue, OfflineScreenshot},
+    pause::{
+        current_pause_status, discard_deferred, DeferredScreenshot, DeferredScreenshots,
+        PauseState, PauseStatus,
+    },
+    pipeline::{accept_pending, process_job, RenamedScreenshot},
+    queue::{Job, JobQueue},
+    rules::{load_rules, save_rules, validate_rules, Rule},
+    sanitize::{detect_filesystem, sanitize_file_name},
+    settings::{load_settings, save_settings, Settings},
+    template::{render_template, CaseStyle, SequenceCounter, TemplateContext},
     user::{Tier, UserDevice},
-    watch::watch_screenshots,
+    watch::{watch_screenshots, watcher_channel, WatcherCommand, WatcherMessage},
 };
 use std::env;
 #[cfg(target_os = "macos")]
 use tauri_plugin_positioner::{Position, WindowExt};
 #[cfg(target_os = "macos")]
 use window_vibrancy::NSVisualEffectState;
+mod catch_up;
+mod detect;
+mod folders;
 mod generate_name;
+mod history;
+mod http;
+mod image_format;
+mod inbox;
+mod ledger;
+mod naming;
+mod offline_queue;
+mod organize;
+mod pause;
+mod pipeline;
+mod queue;
+mod rename;
+mod retry;
+mod rules;
+mod sanitize;
+mod settings;
+mod stability;
 mod state;
+mod store;
+mod template;
 mod user;
 mod watch;
 use state::AppState;
 use std::sync::mpsc;
-// use tauri_plugin_dialog::{DialogExt, FilePath};
+use tauri_plugin_dialog::DialogExt;
 use tauri_plugin_notification::NotificationExt;
 use user::register;
 // was in the icons tauri config file
@@ -39,9 +86,75 @@ fn greet(name: &str) -> String {
 
 #[tauri::command]
 fn set_watcher_paused(state: tauri::State<AppState>, paused: bool) {
-    let mut guard = state.paused.lock().unwrap();
-    *guard = paused;
+    let quiet_hours = state.settings.lock().unwrap().quiet_hours.clone();
+    {
+        let mut guard = state.paused.lock().unwrap();
+        if paused {
+            guard.pause(None);
+        } else {
+            guard.resume(&quiet_hours);
+        }
+    }
     println!("✅ Watcher paused state updated: {}", paused);
+    state.watcher.send(WatcherCommand::PauseChanged);
+}
+
+#[tauri::command]
+fn pause_watcher_for(state: tauri::State<AppState>, minutes: u32) -> Result<PauseStatus, String> {
+    if minutes == 0 {
+        return Err("Pause duration must be at least one minute".to_string());
+    }
+    let until = Utc::now() + chrono::Duration::minutes(minutes as i64);
+    state.paused.lock().unwrap().pause(Some(until));
+    state.watcher.send(WatcherCommand::PauseChanged);
+    Ok(current_pause_status(&state))
+}
+
+#[tauri::command]
+fn pause_watcher_until(
+    state: tauri::State<AppState>,
+    until: DateTime<Utc>,
+) -> Result<PauseStatus, String> {
+    if until <= Utc::now() {
+        return Err("Pause end time must be in the future".to_string());
+    }
+    state.paused.lock().unwrap().pause(Some(until));
+    state.watcher.send(WatcherCommand::PauseChanged);
+    Ok(current_pause_status(&state))
+}
+
+#[tauri::command]
+fn get_pause_status(state: tauri::State<AppState>) -> PauseStatus {
+    current_pause_status(&state)
+}
+
+#[tauri::command]
+fn get_deferred_screenshots(state: tauri::State<AppState>) -> Vec<DeferredScreenshot> {
+    state.deferred.items()
+}
+
+/// Forgets deferred screenshots so they are not named on resume. Discards
+/// everything when `paths` is omitted.
+#[tauri::command]
+fn discard_deferred_screenshots(
+    app: AppHandle,
+    state: tauri::State<AppState>,
+    paths: Option<Vec<PathBuf>>,
+) -> Vec<DeferredScreenshot> {
+    discard_deferred(&app, &state, paths);
+    state.deferred.items()
+}
+
+#[tauri::command]
+fn get_offline_screenshots(state: tauri::State<AppState>) -> Vec<OfflineScreenshot> {
+    state.offline.items()
+}
+
+/// Tries the offline screenshots again now instead of waiting for the
+/// connection check.
+#[tauri::command]
+fn retry_offline_screenshots(app: AppHandle, state: tauri::State<AppState>) {
+    retry_offline(&app, &state);
 }
 
 #[tauri::command]
@@ -57,62 +170,289 @@ fn get_user_tier(state: tauri::State<AppState>) -> Tier {
     }
 }
 
-// #[tauri::command]
-// async fn select_folder(app: tauri::AppHandle) -> Option<String> {
+#[tauri::command]
+fn list_watched_folders(state: tauri::State<AppState>) -> Vec<WatchedFolder> {
+    state.watched_folders.lock().unwrap().clone()
+}
+
+/// Adds a folder to the saved list and starts watching it right away.
+fn watch_folder(app: &AppHandle, path: &str) -> Result<Vec<WatchedFolder>, String> {
+    let state = app.state::<AppState>();
+    let mut folders = state.watched_folders.lock().unwrap();
+    add_folder(&mut folders, path)?;
+    save_watched_folders(app, &folders);
+
+    // add_folder stores the canonical path, which is what notify should watch
+    let added = folders.last().unwrap().path.clone();
+    state
+        .watcher
+        .send(WatcherCommand::Watch(PathBuf::from(&added)));
+    println!("📂 Added watched folder: {}", added);
+    Ok(folders.clone())
+}
+
+#[tauri::command]
+fn add_watched_folder(app: AppHandle, path: String) -> Result<Vec<WatchedFolder>, String> {
+    watch_folder(&app, &path)
+}
+
+#[tauri::command]
+fn remove_watched_folder(
+    app: AppHandle,
+    state: tauri::State<AppState>,
+    path: String,
+) -> Result<Vec<WatchedFolder>, String> {
+    let mut folders = state.watched_folders.lock().unwrap();
+    let removed = remove_folder(&mut folders, &path)?;
+    save_watched_folders(&app, &folders);
+    state
+        .watcher
+        .send(WatcherCommand::Unwatch(PathBuf::from(&removed.path)));
+    println!("🗑️ Removed watched folder: {}", removed.path);
+    Ok(folders.clone())
+}
+
+#[tauri::command]
+fn set_watched_folder_enabled(
+    app: AppHandle,
+    state: tauri::State<AppState>,
+    path: String,
+    enabled: bool,
+) -> Result<Vec<WatchedFolder>, String> {
+    let mut folders = state.watched_folders.lock().unwrap();
+    let folder = set_folder_enabled(&mut folders, &path, enabled)?;
+    state.watcher.send(WatcherCommand::for_folder(folder));
+    println!("✅ Watched folder {} enabled: {}", folder.path, enabled);
+    save_watched_folders(&app, &folders);
+    Ok(folders.clone())
+}
+
+/// Chooses between renaming right away and sending names to the review inbox.
+#[tauri::command]
+fn set_watched_folder_mode(
+    app: AppHandle,
+    state: tauri::State<AppState>,
+    path: String,
+    mode: FolderMode,
+) -> Result<Vec<WatchedFolder>, String> {
+    let mut folders = state.watched_folders.lock().unwrap();
+    set_folder_mode(&mut folders, &path, mode)?;
+    save_watched_folders(&app, &folders);
+    println!("✅ Watched folder {} mode: {:?}", path, mode);
+    Ok(folders.clone())
+}
+
+#[tauri::command]
+fn list_pending_renames(state: tauri::State<AppState>) -> Vec<PendingRename> {
+    state.inbox.items()
+}
+
+/// Renames a screenshot from the review inbox, optionally with an edited name.
+#[tauri::command]
+fn accept_pending_rename(
+    app: AppHandle,
+    id: u64,
+    name: Option<String>,
+) -> Result<RenamedScreenshot, String> {
+    accept_pending(&app, id, name).inspect_err(|e| {
+        eprintln!("❌ Failed to accept pending rename: {}", e);
+    })
+}
+
+/// Drops a suggestion and leaves the screenshot as it is.
+#[tauri::command]
+fn reject_pending_rename(state: tauri::State<AppState>, id: u64) -> Result<(), String> {
+    let item = state
+        .inbox
+        .take(id)
+        .ok_or_else(|| format!("No pending rename with id {}", id))?;
+    println!("🚫 Rejected name for {}", item.path.display());
+    Ok(())
+}
+
+/// Renames newest first; `limit` caps the list for the recent renames view.
+#[tauri::command]
+fn get_rename_history(state: tauri::State<AppState>, limit: Option<usize>) -> Vec<HistoryEntry> {
+    state.history.recent(limit)
+}
+
+#[tauri::command]
+fn undo_rename(
+    app: AppHandle,
+    state: tauri::State<AppState>,
+    id: u64,
+) -> Result<HistoryEntry, String> {
+    let entry = state.history.undo(id).inspect_err(|e| {
+        eprintln!("❌ Failed to undo rename: {}", e);
+    })?;
+    emit_undone(&app, &entry);
+    Ok(entry)
+}
+
+/// Undoes the `n` most recent renames, newest first, reporting the ones that
+/// couldn't be undone alongside the ones that were.
+#[tauri::command]
+fn undo_last_n(app: AppHandle, state: tauri::State<AppState>, n: usize) -> UndoReport {
+    let report = state.history.undo_last(n);
+    for entry in &report.undone {
+        emit_undone(&app, entry);
+    }
+    for failure in &report.failed {
+        eprintln!(
+            "❌ Failed to undo rename #{}: {}",
+            failure.id, failure.error
+        );
+    }
+    report
+}
+
+fn emit_undone(app: &AppHandle, entry: &HistoryEntry) {
+    println!(
+        "↩️ Restored {} to {}",
+        entry.new_path.display(),
+        entry.original_path.display()
+    );
+    if let Err(e) = app.emit_to("main", "rename-undone", entry) {
+        eprintln!("❌ Failed to emit event: {:?}", e);
+    }
+}
+
+#[tauri::command]
+fn get_processing_queue(state: tauri::State<AppState>) -> Vec<Job> {
+    state.queue.jobs()
+}
+
+/// Stops naming a queued or in-flight screenshot, which then keeps its
+/// original name. A request already sent runs to its end, but its answer is
+/// dropped.
+#[tauri::command]
+fn cancel_naming(state: tauri::State<AppState>, job_id: u64) -> Result<(), String> {
+    if !state.queue.jobs().iter().any(|job| job.id == job_id) {
+        return Err(format!("No job with id {}", job_id));
+    }
+    if !state.queue.cancel(job_id) {
+        return Err(format!("Job {} has already finished", job_id));
+    }
+    println!("⏹️ Cancelled naming for job #{}", job_id);
+    Ok(())
+}
+
+#[tauri::command]
+fn get_catch_up_report(state: tauri::State<AppState>) -> Option<CatchUpReport> {
+    state.catch_up.lock().unwrap().clone()
+}
+
+#[tauri::command]
+fn get_settings(state: tauri::State<AppState>) -> Settings {
+    state.settings.lock().unwrap().clone()
+}
+
+#[tauri::command]
+fn update_settings(
+    app: AppHandle,
+    state: tauri::State<AppState>,
+    settings: Settings,
+) -> Result<Settings, String> {
+    settings.validate()?;
+    save_settings(&app, &settings);
+
+    let mut current = state.settings.lock().unwrap();
+    if current.worker_count != settings.worker_count {
+        state.queue.set_worker_count(settings.worker_count);
+    }
+    *current = settings;
+    Ok(current.clone())
+}
 
-//     let mut path = None;
-//     app.dialog().file().pick_folder(move |folder_path| {
+/// Lets the settings screen put an edited naming prompt back to the original.
+#[tauri::command]
+fn get_default_naming_prompt() -> String {
+    DEFAULT_SYSTEM_PROMPT.to_string()
+}
 
-//         if let Some(folder) = folder_path {
+#[tauri::command]
+fn get_rules(state: tauri::State<AppState>) -> Vec<Rule> {
+    state.rules.lock().unwrap().clone()
+}
 
-//             let folder_str = folder.to_string();
-//             println!("📂 Selected folder: {}", folder_str);
-//             path = Some(folder_str);
-//         }
-//         else{
-//             println!("❌ No folder selected");
-//         }
-//         // do something with the optional folder path here
-//         // the folder path is `None` if the user closed the dialog
-//     });
-//     return path;
-// }
+#[tauri::command]
+fn set_rules(
+    app: AppHandle,
+    state: tauri::State<AppState>,
+    rules: Vec<Rule>,
+) -> Result<(), String> {
+    validate_rules(&rules)?;
+    save_rules(&app, &rules);
+    *state.rules.lock().unwrap() = rules;
+    println!("📐 Rules updated");
+    Ok(())
+}
 
+/// Shows what a screenshot taken now would be named with `template`.
+#[tauri::command]
+fn preview_name_template(
+    state: tauri::State<AppState>,
+    template: String,
+    case: CaseStyle,
+) -> Result<String, String> {
+    let folders = state.watched_folders.lock().unwrap().clone();
+    let seq = state.sequence.peek(Local::now().date_naive());
+    let context = TemplateContext::sample(&folders, seq);
+    let name = render_template(&template, case, &context)?;
+
+    let fs = match folders.first() {
+        Some(folder) => detect_filesystem(std::path::Path::new(&folder.path)),
+        None => detect_filesystem(std::path::Path::new(".")),
+    };
+    sanitize_file_name(&name, "png", fs)
+}
 
+#[tauri::command]
+fn get_detection_rules(app: AppHandle) -> DetectionRules {
+    load_detection_rules(&app)
+}
 
-// tauri-plugin-dialog = "2.3.2"
+#[tauri::command]
+fn set_detection_rules(
+    app: AppHandle,
+    state: tauri::State<AppState>,
+    rules: DetectionRules,
+) -> Result<(), String> {
+    // Compile first so a bad pattern is reported instead of saved
+    let matcher = rules.compile()?;
+    save_detection_rules(&app, &rules);
+    state.watcher.send(WatcherCommand::SetRules(matcher));
+    Ok(())
+}
 
-// #[tauri::command]
-// async fn select_folder(app: AppHandle) -> Option<String> {
+/// Opens the native folder picker and watches whatever the user chose.
+/// Blocks until the dialog closes, so never call it from the main thread.
+fn pick_and_watch_folder(app: &AppHandle) -> Result<Option<String>, String> {
+    let Some(folder) = app.dialog().file().blocking_pick_folder() else {
+        println!("❌ No folder selected");
+        return Ok(None);
+    };
 
-//     let folder_path = app.dialog().file().blocking_pick_folder();
+    let path = folder.into_path().map_err(|e| e.to_string())?;
+    let path = path.to_string_lossy().to_string();
+    println!("📂 Selected folder: {}", path);
 
-//     let res = match folder_path {
-//         Some(path) => {
-//             println!("📂 Selected folder: {}", path.to_string());
-//             // let state = app.state::<AppState>();
-//             // let mut selected_path = state.selected_path.lock().unwrap();
-//             // *selected_path = path.to_string_lossy().to_string();
-//             Some(path.to_string())
-//         }
-//         None => {
-//             println!("❌ No folder selected");
-//             None
-//         }
-//     };
-//     res
+    watch_folder(app, &path)?;
+    Ok(Some(path))
+}
 
-// }
+#[tauri::command]
+async fn select_folder(app: AppHandle) -> Result<Option<String>, String> {
+    pick_and_watch_folder(&app)
+}
 
 fn spawn_watcher_thread(
-    paused_state: Arc<Mutex<bool>>,
     app_handle: AppHandle,
-    user_device: UserDevice,
-    directory_to_watch: Arc<Mutex<String>>,
+    state: AppState,
+    rx: mpsc::Receiver<WatcherMessage>,
 ) {
     std::thread::spawn(move || {
-        if let Err(e) = watch_screenshots(paused_state, app_handle, user_device, directory_to_watch)
-        {
+        if let Err(e) = watch_screenshots(app_handle, state, rx) {
             eprintln!("❌ Error in watcher: {:?}", e);
         }
     });
@@ -141,21 +481,41 @@ pub fn run() {
                 sentry::Level::Info,
             );
 
+            let settings = load_settings(app.app_handle());
+
+            let ledger = ProcessedLedger::load(app.app_handle());
+
+            let worker_device = user_device.clone();
+            let worker_ledger = ledger.clone();
+            let worker_handle = app.app_handle().clone();
+            let queue = JobQueue::new(Some(app.app_handle().clone()), move |queue, job| {
+                process_job(queue, job, &worker_device, &worker_ledger, &worker_handle)
+            });
+            queue.set_worker_count(settings.worker_count);
+
+            let (watcher_control, watcher_rx) = watcher_channel();
             let state = AppState {
-                paused: Arc::new(Mutex::new(false)),
+                paused: Arc::new(Mutex::new(PauseState::default())),
                 user_device: user_device,
-                selected_path: Arc::new(Mutex::new(String::new())),
+                watched_folders: Arc::new(Mutex::new(load_watched_folders(app.app_handle()))),
+                watcher: watcher_control,
+                queue: queue,
+                settings: Arc::new(Mutex::new(settings)),
+                catch_up: Arc::new(Mutex::new(None)),
+                deferred: DeferredScreenshots::load(app.app_handle()),
+                ledger: ledger,
+                sequence: SequenceCounter::load(app.app_handle()),
+                rules: Arc::new(Mutex::new(load_rules(app.app_handle()))),
+                inbox: ReviewInbox::load(app.app_handle()),
+                history: RenameHistory::load(app.app_handle()),
+                offline: OfflineQueue::load(app.app_handle()),
             };
             app.manage(state.clone());
 
             let app_handle = app.app_handle().clone(); // clone app handle for thread
 
-            spawn_watcher_thread(
-                state.paused.clone(),
-                app_handle,
-                state.user_device.clone(),
-                state.selected_path.clone(),
-            );
+            spawn_watcher_thread(app_handle.clone(), state.clone(), watcher_rx);
+            spawn_offline_retry(app_handle);
 
             sentry::capture_message("About to get user device", sentry::Level::Info);
             app.notification()
@@ -270,22 +630,14 @@ pub fn run() {
                         println!("quit menu item was clicked");
                         app.exit(0);
                     }
-                    // "settings" => {
-                    //     let app_handle = app.app_handle().clone();
-                    //     std::thread::spawn(move || {
-                    //         if let Some(folder_path) = select_folder(app_handle.clone()) {
-
-                    //             // https://docs.rs/tauri-plugin-dialog/2.3.2/tauri_plugin_dialog/struct.FileDialogBuilder.html
-                    //             let state = app_handle.state::<AppState>();
-                    //             let paused = state.paused.clone();
-                    //             let user_device = state.user_device.clone();
-
-                    //             if let Err(e) = watch_screenshots(paused, app_handle, user_device) {
-                    //                 eprintln!("❌ Error in watcher: {:?}", e);
-                    //             }
-                    //         }
-                    //     });
-                    // }
+                    "settings" => {
+                        let app_handle = app.app_handle().clone();
+                        std::thread::spawn(move || {
+                            if let Err(e) = pick_and_watch_folder(&app_handle) {
+                                eprintln!("❌ Failed to add watched folder: {}", e);
+                            }
+                        });
+                    }
                     _ => {
                         println!("menu item {:?} not handled", event.id);
                     }
@@ -297,9 +649,38 @@ pub fn run() {
         .invoke_handler(tauri::generate_handler![
             greet,
             set_watcher_paused,
+            pause_watcher_for,
+            pause_watcher_until,
+            get_pause_status,
             get_device_id,
             get_user_tier,
-            // select_folder
+            list_watched_folders,
+            add_watched_folder,
+            remove_watched_folder,
+            set_watched_folder_enabled,
+            set_watched_folder_mode,
+            list_pending_renames,
+            accept_pending_rename,
+            reject_pending_rename,
+            get_rename_history,
+            undo_rename,
+            undo_last_n,
+            select_folder,
+            preview_name_template,
+            get_default_naming_prompt,
+            get_rules,
+            set_rules,
+            get_detection_rules,
+            set_detection_rules,
+            get_processing_queue,
+            cancel_naming,
+            get_catch_up_report,
+            get_deferred_screenshots,
+            get_offline_screenshots,
+            retry_offline_screenshots,
+            discard_deferred_screenshots,
+            get_settings,
+            update_settings,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,1024 @@
+use base64::{engine::general_purpose, Engine};
+use chrono::{DateTime, Utc};
+use reqwest::{blocking::Response, header::RETRY_AFTER};
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+use tauri::AppHandle;
+
+use crate::{
+    generate_name::encode_image_to_base64,
+    image_format::{convert_to_png, detect_file_format, ImageFormat},
+    store::data_file,
+    user::UserDevice,
+};
+
+mod hosted;
+mod local;
+mod offline;
+mod openai;
+
+pub use hosted::HostedGenerator;
+pub use local::{name_from_text, LocalGenerator, DETECTION_MODEL, RECOGNITION_MODEL};
+pub use offline::{
+    color_name, dominant_color, fallback_name, original_capture_time, parse_capture_time,
+    png_title, OfflineGenerator,
+};
+pub use openai::OpenAiGenerator;
+
+/// A backend that turns a screenshot into a short descriptive name.
+pub trait NameGenerator: Send + Sync {
+    /// Shown in logs
+    fn label(&self) -> &'static str;
+
+    /// Suggests a name for the screenshot at `path`, without extension.
+    fn generate(&self, path: &Path) -> Result<String, NamingError>;
+
+    /// Server the backend talks to, checked before retrying screenshots that
+    /// failed while offline. `None` for backends that work without a network.
+    fn server_url(&self) -> Option<String> {
+        None
+    }
+}
+
+/// A name and where it came from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NamingOutcome {
+    pub name: String,
+    /// Label of the backend that produced `name`
+    pub backend: &'static str,
+    /// Why the chosen backend failed, when `name` is a fallback
+    pub fallback_reason: Option<NamingError>,
+    /// Time spent waiting for the file to finish being written
+    pub wait_ms: u64,
+}
+
+/// Why a screenshot couldn't be named. Serialized with a `kind` tag so the UI
+/// can tell the cases apart.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(
+    tag = "kind",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum NamingError {
+    /// Missing, still being written, or not an image we can read
+    FileUnavailable { message: String },
+    /// No response: offline, DNS, refused connection, timeout
+    Network { message: String },
+    /// The server answered with an unexpected status
+    HttpStatus {
+        status: u16,
+        /// Seconds the server asked us to wait, from `Retry-After`
+        #[serde(skip_serializing_if = "Option::is_none")]
+        retry_after: Option<u64>,
+    },
+    /// The response wasn't in the expected shape
+    Parse { message: String },
+    /// The SnapName free plan is used up
+    QuotaExceeded,
+    /// The backend answered but gave no usable name
+    Rejected { message: String },
+    /// Stopped from the UI before an answer came
+    Cancelled,
+}
+
+impl NamingError {
+    /// Short name of the variant, as used in the `kind` tag.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            NamingError::FileUnavailable { .. } => "fileUnavailable",
+            NamingError::Network { .. } => "network",
+            NamingError::HttpStatus { .. } => "httpStatus",
+            NamingError::Parse { .. } => "parse",
+            NamingError::QuotaExceeded => "quotaExceeded",
+            NamingError::Rejected { .. } => "rejected",
+            NamingError::Cancelled => "cancelled",
+        }
+    }
+
+    pub fn file_unavailable(message: impl Into<String>) -> Self {
+        NamingError::FileUnavailable {
+            message: message.into(),
+        }
+    }
+
+    pub fn rejected(message: impl Into<String>) -> Self {
+        NamingError::Rejected {
+            message: message.into(),
+        }
+    }
+
+    /// An error status, with the `Retry-After` header when the server sent one.
+    pub fn from_response(response: &Response) -> Self {
+        let retry_after = response
+            .headers()
+            .get(RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| parse_retry_after(value, Utc::now()));
+        NamingError::HttpStatus {
+            status: response.status().as_u16(),
+            retry_after,
+        }
+    }
+
+    /// Whether trying again later might succeed: connection problems and
+    /// server errors, but not bad input, a used-up quota or a refusal.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            NamingError::Network { .. } => true,
+            NamingError::HttpStatus { status, .. } => (500..=599).contains(status),
+            _ => false,
+        }
+    }
+}
+
+/// `Retry-After` is either a number of seconds or an HTTP date.
+pub fn parse_retry_after(value: &str, now: DateTime<Utc>) -> Option<u64> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(seconds);
+    }
+    let at = DateTime::parse_from_rfc2822(value).ok()?;
+    Some((at.with_timezone(&Utc) - now).num_seconds().max(0) as u64)
+}
+
+impl From<reqwest::Error> for NamingError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_decode() {
+            NamingError::Parse {
+                message: e.to_string(),
+            }
+        } else if let Some(status) = e.status() {
+            NamingError::HttpStatus {
+                status: status.as_u16(),
+                retry_after: None,
+            }
+        } else {
+            NamingError::Network {
+                message: e.to_string(),
+            }
+        }
+    }
+}
+
+impl fmt::Display for NamingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NamingError::FileUnavailable { message } => write!(f, "File unavailable: {}", message),
+            NamingError::Network { message } => write!(f, "Network error: {}", message),
+            NamingError::HttpStatus { status, .. } => {
+                write!(f, "Server returned unexpected status: {}", status)
+            }
+            NamingError::Parse { message } => {
+                write!(f, "Failed to parse JSON response: {}", message)
+            }
+            NamingError::QuotaExceeded => write!(f, "Free plan limit reached"),
+            NamingError::Rejected { message } => write!(f, "No name suggested: {}", message),
+            NamingError::Cancelled => write!(f, "Naming was cancelled"),
+        }
+    }
+}
+
+/// Which backend names screenshots.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NamingProvider {
+    /// The SnapName service, subject to the plan's quota
+    #[default]
+    Hosted,
+    /// Any server speaking the OpenAI chat completions API with images
+    OpenAi,
+    /// Built from the file alone; nothing leaves the machine
+    Offline,
+    /// Text read by an OCR model on this machine; needs the `local-ocr` feature
+    Local,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct NamingSettings {
+    pub provider: NamingProvider,
+    pub openai: OpenAiSettings,
+    pub local: LocalSettings,
+    /// What to do when the backend can't name a screenshot
+    pub fallback: FallbackMode,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FallbackMode {
+    /// Name it from the file itself, like the offline backend
+    #[default]
+    Describe,
+    /// Leave it under its original name
+    Keep,
+}
+
+impl FallbackMode {
+    /// What becomes of a screenshot the backend failed to name: a name built
+    /// from the file, or `error` so it keeps its original name.
+    pub fn outcome(
+        self,
+        path: &Path,
+        error: NamingError,
+        wait_ms: u64,
+    ) -> Result<NamingOutcome, NamingError> {
+        if error == NamingError::Cancelled {
+            // The user chose to leave it alone
+            return Err(error);
+        }
+        if error.is_transient() {
+            // Worth another try once back online, rather than settling for less
+            return Err(error);
+        }
+        match self {
+            FallbackMode::Describe => Ok(NamingOutcome {
+                name: fallback_name(path),
+                backend: OfflineGenerator.label(),
+                fallback_reason: Some(error),
+                wait_ms,
+            }),
+            FallbackMode::Keep => Err(error),
+        }
+    }
+}
+
+/// Where the on-device OCR models live and how long names may be.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct LocalSettings {
+    /// Folder holding the `.rten` model files; empty means `models` in the
+    /// app data dir
+    pub model_dir: String,
+    pub max_words: usize,
+}
+
+impl Default for LocalSettings {
+    fn default() -> Self {
+        LocalSettings {
+            model_dir: String::new(),
+            max_words: 6,
+        }
+    }
+}
+
+/// Instructions given to OpenAI-compatible models unless the user edits them.
+pub const DEFAULT_SYSTEM_PROMPT: &str = "You name screenshots. Look at the image and reply \
+with a short, specific file name describing what it shows, such as the app, page or error. \
+Reply with the name only: no extension, quotes or explanation.";
+
+/// Default folder for on-device models, inside the app data dir.
+const MODELS_DIR: &str = "models";
+
+/// Most words a model may be asked for.
+const MAX_WORDS_LIMIT: usize = 20;
+
+/// Connection details and prompt for an OpenAI-compatible endpoint.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct OpenAiSettings {
+    /// Up to and including the version, e.g. `https://api.openai.com/v1`
+    pub base_url: String,
+    pub model: String,
+    /// Sent as a bearer token; left out when empty, e.g. for a local server
+    pub api_key: String,
+    pub system_prompt: String,
+    /// Longer replies are cut to this many words
+    pub max_words: usize,
+    pub style: NameStyle,
+}
+
+impl Default for OpenAiSettings {
+    fn default() -> Self {
+        OpenAiSettings {
+            base_url: "https://api.openai.com/v1".to_string(),
+            model: "gpt-4o-mini".to_string(),
+            api_key: String::new(),
+            system_prompt: DEFAULT_SYSTEM_PROMPT.to_string(),
+            max_words: 6,
+            style: NameStyle::Descriptive,
+        }
+    }
+}
+
+/// How the model should phrase names. Casing is left to the name template.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NameStyle {
+    /// A short phrase, e.g. `login page error`
+    #[default]
+    Descriptive,
+    /// Key terms only, most important first, e.g. `stripe invoice march`
+    Keywords,
+}
+
+impl NameStyle {
+    fn instruction(self) -> &'static str {
+        match self {
+            NameStyle::Descriptive => "Write the name as a short descriptive phrase.",
+            NameStyle::Keywords => {
+                "Write the name as key terms only, most important first, without filler words."
+            }
+        }
+    }
+}
+
+impl OpenAiSettings {
+    /// System prompt with the output constraints appended.
+    pub fn prompt(&self) -> String {
+        format!(
+            "{}\n\n{} Use at most {} words.",
+            self.system_prompt.trim(),
+            self.style.instruction(),
+            self.max_words
+        )
+    }
+}
+
+impl NamingSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        match self.provider {
+            NamingProvider::OpenAi => self.validate_openai(),
+            NamingProvider::Local => self.validate_local(),
+            NamingProvider::Hosted | NamingProvider::Offline => Ok(()),
+        }
+    }
+
+    fn validate_local(&self) -> Result<(), String> {
+        if !cfg!(feature = "local-ocr") {
+            return Err("This build doesn't include on-device naming".to_string());
+        }
+        if !self.local.model_dir.is_empty() && !Path::new(&self.local.model_dir).is_absolute() {
+            return Err(format!(
+                "Model folder must be an absolute path: {}",
+                self.local.model_dir
+            ));
+        }
+        if self.local.max_words == 0 || self.local.max_words > MAX_WORDS_LIMIT {
+            return Err(format!(
+                "Maximum words must be between 1 and {}",
+                MAX_WORDS_LIMIT
+            ));
+        }
+        Ok(())
+    }
+
+    fn validate_openai(&self) -> Result<(), String> {
+        let base_url = self.openai.base_url.trim();
+        if !base_url.starts_with("http://") && !base_url.starts_with("https://") {
+            return Err(format!(
+                "Naming server URL must start with http:// or https://: {}",
+                base_url
+            ));
+        }
+        if self.openai.model.trim().is_empty() {
+            return Err("Naming model can't be empty".to_string());
+        }
+        if self.openai.system_prompt.trim().is_empty() {
+            return Err("Naming prompt can't be empty".to_string());
+        }
+        if self.openai.max_words == 0 || self.openai.max_words > MAX_WORDS_LIMIT {
+            return Err(format!(
+                "Maximum words must be between 1 and {}",
+                MAX_WORDS_LIMIT
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// The backend chosen in settings.
+pub fn generator_for(
+    settings: &NamingSettings,
+    user_device: &UserDevice,
+    app_handle: &AppHandle,
+) -> Box<dyn NameGenerator> {
+    match settings.provider {
+        NamingProvider::Hosted => Box::new(HostedGenerator::for_device(user_device)),
+        NamingProvider::OpenAi => Box::new(OpenAiGenerator::new(&settings.openai)),
+        NamingProvider::Offline => Box::new(OfflineGenerator),
+        NamingProvider::Local => {
+            let model_dir = if settings.local.model_dir.is_empty() {
+                data_file(app_handle, MODELS_DIR).unwrap_or_else(|| PathBuf::from(MODELS_DIR))
+            } else {
+                PathBuf::from(&settings.local.model_dir)
+            };
+            Box::new(LocalGenerator::new(&model_dir, settings.local.max_words))
+        }
+    }
+}
+
+/// Tidies a backend's reply into a bare name, keeping at most `max_words`
+/// words. Returns `None` if nothing usable is left. Sanitizing for the
+/// filesystem happens later, the same way for every backend.
+pub fn clean_name(reply: &str, max_words: Option<usize>) -> Option<String> {
+    // Models sometimes add an explanation on a second line
+    let line = reply.lines().map(str::trim).find(|line| !line.is_empty())?;
+    let line = strip_label(line);
+    let line =
+        line.trim_matches(|c: char| matches!(c, '"' | '\'' | '`' | '*') || c.is_whitespace());
+    let line = strip_image_extension(line).trim_end_matches('.');
+
+    let words = line.split_whitespace();
+    let name = match max_words {
+        Some(max) => words.take(max).collect::<Vec<_>>().join(" "),
+        None => words.collect::<Vec<_>>().join(" "),
+    };
+    (!name.is_empty()).then_some(name)
+}
+
+/// Drops a leading `Name:` or `Filename:` some models echo back.
+fn strip_label(line: &str) -> &str {
+    for label in ["file name:", "filename:", "name:"] {
+        if line.len() >= label.len()
+            && line.is_char_boundary(label.len())
+            && line[..label.len()].eq_ignore_ascii_case(label)
+        {
+            return line[label.len()..].trim_start();
+        }
+    }
+    line
+}
+
+fn strip_image_extension(name: &str) -> &str {
+    match name.rsplit_once('.') {
+        Some((stem, ext))
+            if ["png", "jpg", "jpeg", "heic", "webp", "gif", "tiff"]
+                .contains(&ext.to_ascii_lowercase().as_str()) =>
+        {
+            stem
+        }
+        _ => name,
+    }
+}
+
+/// Builds the `data:` URL sent to a backend, labelled with the real format
+/// and converted to PNG when backends can't read the original.
+pub fn image_data_url(path: &Path) -> Result<String, String> {
+    let format = detect_file_format(path)
+        .ok_or_else(|| format!("Unrecognized image format: {}", path.display()))?;
+
+    if format.is_accepted_by_backend() {
+        let encoded = encode_image_to_base64(path);
+        if encoded.is_empty() {
+            return Err(format!("Failed to read {}", path.display()));
+        }
+        return Ok(format!("data:{};base64,{}", format.mime_type(), encoded));
+    }
+
+    println!("🔄 Converting {:?} screenshot to PNG", format);
+    let png = convert_to_png(path, format)?;
+    Ok(format!(
+        "data:{};base64,{}",
+        ImageFormat::Png.mime_type(),
+        general_purpose::STANDARD.encode(&png)
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{NaiveDate, TimeZone, Utc};
+    use std::fs;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::path::{Path, PathBuf};
+    use std::sync::mpsc::{self, Receiver};
+    use std::thread;
+
+    /// Serves one canned response on a local port and hands back the raw request.
+    fn stub_server(status: u16, body: &str) -> (String, Receiver<String>) {
+        stub_server_with_headers(status, "", body)
+    }
+
+    /// `headers` are extra `Name: value\r\n` lines for the response.
+    fn stub_server_with_headers(
+        status: u16,
+        headers: &str,
+        body: &str,
+    ) -> (String, Receiver<String>) {
+        let headers = headers.to_string();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let body = body.to_string();
+        let (tx, rx) = mpsc::channel();
+
+        thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut request = String::new();
+            let mut content_length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if let Some(value) = line.to_lowercase().strip_prefix("content-length:") {
+                    content_length = value.trim().parse().unwrap();
+                }
+                request.push_str(&line);
+                if line == "\r\n" {
+                    break;
+                }
+            }
+            let mut request_body = vec![0; content_length];
+            reader.read_exact(&mut request_body).unwrap();
+            request.push_str(&String::from_utf8_lossy(&request_body));
+            tx.send(request).unwrap();
+
+            let response = format!(
+                "HTTP/1.1 {} Stub\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n{}\r\n{}",
+                status,
+                body.len(),
+                headers,
+                body
+            );
+            reader.get_mut().write_all(response.as_bytes()).unwrap();
+        });
+        (url, rx)
+    }
+
+    fn screenshot(name: &str) -> PathBuf {
+        fs::create_dir_all("tests/assets").unwrap();
+        let path = PathBuf::from("tests/assets").join(name);
+        image::RgbImage::new(8, 6).save(&path).unwrap();
+        path
+    }
+
+    fn openai(base_url: &str, api_key: &str) -> OpenAiGenerator {
+        OpenAiGenerator::new(&OpenAiSettings {
+            base_url: base_url.to_string(),
+            model: "llava".to_string(),
+            api_key: api_key.to_string(),
+            max_words: 3,
+            ..OpenAiSettings::default()
+        })
+    }
+
+    #[test]
+    fn test_hosted_returns_suggested_name() {
+        let path = screenshot("naming_hosted.png");
+        let (url, requests) = stub_server(
+            200,
+            r#"{"success": true, "message": "ok", "data": {"screenshotName": " login page error "}}"#,
+        );
+
+        let generator = HostedGenerator::new(&url, "device-1", "1.2.3");
+        assert_eq!(generator.generate(&path).unwrap(), "login page error");
+
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("POST / "));
+        assert!(request.contains(r#""deviceId":"device-1""#));
+        assert!(request.contains(r#""appVersion":"1.2.3""#));
+        assert!(request.contains("data:image/png;base64,"));
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_hosted_errors() {
+        let path = screenshot("naming_hosted_errors.png");
+
+        let (url, _requests) = stub_server(429, "{}");
+        let generator = HostedGenerator::new(&url, "device-1", "1.2.3");
+        assert_eq!(generator.generate(&path), Err(NamingError::QuotaExceeded));
+
+        let (url, _requests) = stub_server(500, "{}");
+        let generator = HostedGenerator::new(&url, "device-1", "1.2.3");
+        assert_eq!(
+            generator.generate(&path),
+            Err(NamingError::HttpStatus {
+                status: 500,
+                retry_after: None
+            })
+        );
+
+        let (url, _requests) = stub_server(200, "not json");
+        let generator = HostedGenerator::new(&url, "device-1", "1.2.3");
+        assert!(matches!(
+            generator.generate(&path),
+            Err(NamingError::Parse { .. })
+        ));
+
+        let (url, _requests) = stub_server(200, r#"{"success": false, "message": "nope"}"#);
+        let generator = HostedGenerator::new(&url, "device-1", "1.2.3");
+        assert_eq!(
+            generator.generate(&path),
+            Err(NamingError::rejected("nope"))
+        );
+
+        // Nothing listening
+        let generator = HostedGenerator::new("http://127.0.0.1:1", "device-1", "1.2.3");
+        assert!(matches!(
+            generator.generate(&path),
+            Err(NamingError::Network { .. })
+        ));
+
+        assert!(matches!(
+            generator.generate(Path::new("/nonexistent/naming.png")),
+            Err(NamingError::FileUnavailable { .. })
+        ));
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_openai_compatible_request_and_response() {
+        let path = screenshot("naming_openai.png");
+        let (url, requests) = stub_server(
+            200,
+            r#"{"choices": [{"message": {"role": "assistant", "content": "\"Invoice March 2026\"\nIt shows an invoice."}}]}"#,
+        );
+
+        let generator = openai(&format!("{}/v1/", url), "sk-test");
+        assert_eq!(generator.generate(&path).unwrap(), "Invoice March 2026");
+
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("POST /v1/chat/completions "));
+        assert!(request
+            .to_lowercase()
+            .contains("authorization: bearer sk-test"));
+        assert!(request.contains(r#""model":"llava""#));
+        assert!(request.contains(r#""type":"image_url""#));
+        assert!(request.contains("Use at most 3 words."));
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_openai_compatible_without_key_or_name() {
+        let path = screenshot("naming_openai_empty.png");
+        let (url, requests) = stub_server(200, r#"{"choices": [{"message": {"content": "  "}}]}"#);
+
+        let generator = openai(&url, "");
+        assert!(matches!(
+            generator.generate(&path),
+            Err(NamingError::Rejected { .. })
+        ));
+        // Local servers like Ollama take no key
+        let request = requests.recv().unwrap();
+        assert!(!request.to_lowercase().contains("authorization:"));
+
+        // A rate limit from someone else's server isn't the SnapName quota
+        let (url, _requests) = stub_server(429, "{}");
+        assert_eq!(
+            openai(&url, "sk-test").generate(&path),
+            Err(NamingError::HttpStatus {
+                status: 429,
+                retry_after: None
+            })
+        );
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_clean_name() {
+        assert_eq!(
+            clean_name("  login page error \n", None).unwrap(),
+            "login page error"
+        );
+        assert_eq!(
+            clean_name("\"Login Page Error.png\"\nThis shows a login form.", None).unwrap(),
+            "Login Page Error"
+        );
+        assert_eq!(
+            clean_name("Filename: `stripe invoice`", None).unwrap(),
+            "stripe invoice"
+        );
+        assert_eq!(
+            clean_name("**release notes v2.1**", None).unwrap(),
+            "release notes v2.1"
+        );
+        assert_eq!(
+            clean_name("a very long name from a chatty model", Some(4)).unwrap(),
+            "a very long name"
+        );
+        assert_eq!(clean_name("\n  \"\" \n", None), None);
+    }
+
+    #[test]
+    fn test_prompt_includes_constraints() {
+        let settings = OpenAiSettings {
+            system_prompt: "Name it for a lawyer.".to_string(),
+            max_words: 4,
+            style: NameStyle::Keywords,
+            ..OpenAiSettings::default()
+        };
+        let prompt = settings.prompt();
+        assert!(prompt.starts_with("Name it for a lawyer."));
+        assert!(prompt.contains("key terms only"));
+        assert!(prompt.ends_with("Use at most 4 words."));
+    }
+
+    #[test]
+    fn test_openai_sends_edited_prompt() {
+        let path = screenshot("naming_openai_prompt.png");
+        let (url, requests) = stub_server(
+            200,
+            r#"{"choices": [{"message": {"content": "Name: contract signature page draft"}}]}"#,
+        );
+
+        let generator = OpenAiGenerator::new(&OpenAiSettings {
+            base_url: url,
+            system_prompt: "Name screenshots for a law firm.".to_string(),
+            max_words: 2,
+            style: NameStyle::Keywords,
+            ..OpenAiSettings::default()
+        });
+        // Same clean-up as the hosted service, cut to the word limit
+        assert_eq!(generator.generate(&path).unwrap(), "contract signature");
+
+        let request = requests.recv().unwrap();
+        assert!(request.contains("Name screenshots for a law firm."));
+        assert!(!request.contains(DEFAULT_SYSTEM_PROMPT));
+        assert!(request.contains("key terms only"));
+        assert!(request.contains("Use at most 2 words."));
+        assert!(request.contains(r#""max_tokens":24"#));
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_openai_settings_keep_defaults_for_missing_fields() {
+        let settings: OpenAiSettings =
+            serde_json::from_str(r#"{"baseUrl": "http://localhost:11434/v1", "model": "llava"}"#)
+                .unwrap();
+        assert_eq!(settings.model, "llava");
+        assert_eq!(settings.system_prompt, DEFAULT_SYSTEM_PROMPT);
+        assert_eq!(settings.style, NameStyle::Descriptive);
+
+        let settings: OpenAiSettings =
+            serde_json::from_str(r#"{"systemPrompt": "Be brief.", "style": "keywords"}"#).unwrap();
+        assert_eq!(
+            settings.prompt(),
+            format!(
+                "Be brief.\n\n{} Use at most 6 words.",
+                NameStyle::Keywords.instruction()
+            )
+        );
+    }
+
+    #[test]
+    fn test_name_from_ocr_text() {
+        let text = "| —\nInvoice #1042 — Acme Corp\nTotal due: $1,200.00";
+        assert_eq!(name_from_text(text, 6).unwrap(), "Invoice 1042 Acme Corp");
+        assert_eq!(name_from_text(text, 2).unwrap(), "Invoice 1042");
+        assert_eq!(name_from_text("Settings\n\nx", 6).unwrap(), "Settings");
+        assert_eq!(name_from_text(" | ~ \n —", 6), None);
+    }
+
+    #[test]
+    fn test_parse_capture_time_from_original_name() {
+        let at = |h, m, s| {
+            NaiveDate::from_ymd_opt(2025, 6, 12)
+                .unwrap()
+                .and_hms_opt(h, m, s)
+                .unwrap()
+        };
+        let names = [
+            ("Screenshot 2025-06-12 at 10.22.33", at(10, 22, 33)),
+            ("Screen Shot 2025-06-12 at 1.02.03 PM", at(13, 2, 3)),
+            ("Screen Shot 2025-06-12 at 12.02.03 AM", at(0, 2, 3)),
+            ("Screenshot from 2025-06-12 10-22-33", at(10, 22, 33)),
+            ("Screenshot_20250612_102233", at(10, 22, 33)),
+            ("Screenshot 2025-06-12 102233", at(10, 22, 33)),
+        ];
+        for (name, expected) in names {
+            assert_eq!(parse_capture_time(name), Some(expected), "{}", name);
+        }
+        assert_eq!(parse_capture_time("Screenshot (3)"), None);
+        assert_eq!(
+            parse_capture_time("Screenshot 2025-13-40 at 10.22.33"),
+            None
+        );
+    }
+
+    /// A 10x4 capture named with its time, in a directory of its own so
+    /// tests using the same name don't race.
+    fn timed_capture(dir: &str, color: [u8; 3]) -> PathBuf {
+        let dir = PathBuf::from("tests/assets").join(dir);
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("Screenshot 2025-06-12 at 10.22.33.png");
+        image::RgbImage::from_pixel(10, 4, image::Rgb(color))
+            .save(&path)
+            .unwrap();
+        path
+    }
+
+    #[test]
+    fn test_fallback_name_uses_original_time() {
+        let path = timed_capture("naming_fallback_time", [0, 122, 255]);
+
+        assert_eq!(fallback_name(&path), "blue 10x4 2025-06-12 10.22.33");
+
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn test_png_title_chunk() {
+        let path = screenshot("naming_png_title.png");
+        let mut png = fs::read(&path).unwrap();
+        // Right after the 8-byte signature and the 25-byte IHDR chunk
+        let text = b"Title\0Quarterly   report";
+        let mut chunk = (text.len() as u32).to_be_bytes().to_vec();
+        chunk.extend_from_slice(b"tEXt");
+        chunk.extend_from_slice(text);
+        chunk.extend_from_slice(&[0; 4]);
+        png.splice(33..33, chunk);
+        fs::write(&path, &png).unwrap();
+
+        assert_eq!(png_title(&path).unwrap(), "Quarterly report");
+        assert!(fallback_name(&path).starts_with("Quarterly report "));
+
+        fs::remove_file(&path).unwrap();
+        let plain = screenshot("naming_png_plain.png");
+        assert_eq!(png_title(&plain), None);
+        fs::remove_file(&plain).unwrap();
+    }
+
+    #[test]
+    fn test_color_names() {
+        assert_eq!(color_name([0, 0, 0]), "black");
+        assert_eq!(color_name([255, 255, 255]), "white");
+        assert_eq!(color_name([128, 128, 128]), "gray");
+        assert_eq!(color_name([230, 30, 30]), "red");
+        assert_eq!(color_name([255, 150, 0]), "orange");
+        assert_eq!(color_name([120, 70, 20]), "brown");
+        assert_eq!(color_name([40, 200, 60]), "green");
+        assert_eq!(color_name([0, 122, 255]), "blue");
+        assert_eq!(color_name([140, 60, 220]), "purple");
+    }
+
+    #[test]
+    fn test_local_needs_feature() {
+        let settings = NamingSettings {
+            provider: NamingProvider::Local,
+            ..NamingSettings::default()
+        };
+        assert_eq!(settings.validate().is_ok(), cfg!(feature = "local-ocr"));
+    }
+
+    #[test]
+    fn test_offline_is_deterministic() {
+        let path = screenshot("naming_offline.png");
+        let first = OfflineGenerator.generate(&path).unwrap();
+        assert_eq!(OfflineGenerator.generate(&path).unwrap(), first);
+        // An all-black 8x6 image, followed by the capture time
+        assert!(first.starts_with("black 8x6 "), "{}", first);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_fallback_modes() {
+        let path = timed_capture("naming_fallback_modes", [230, 30, 30]);
+
+        let outcome = FallbackMode::Describe
+            .outcome(&path, NamingError::QuotaExceeded, 120)
+            .unwrap();
+        assert_eq!(outcome.name, "red 10x4 2025-06-12 10.22.33");
+        assert_eq!(outcome.backend, "offline");
+        assert_eq!(outcome.fallback_reason, Some(NamingError::QuotaExceeded));
+        assert_eq!(outcome.wait_ms, 120);
+
+        let rejected = NamingError::rejected("nope");
+        assert_eq!(
+            FallbackMode::Keep.outcome(&path, rejected.clone(), 0),
+            Err(rejected)
+        );
+
+        // Never settled for while the screenshot can still get a real name
+        let unavailable = NamingError::HttpStatus {
+            status: 503,
+            retry_after: None,
+        };
+        for error in [unavailable, NamingError::Cancelled] {
+            assert_eq!(
+                FallbackMode::Describe.outcome(&path, error.clone(), 0),
+                Err(error)
+            );
+        }
+
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn test_fallback_name_for_unreadable_image() {
+        // Not an image and no time in the name: the file time still keeps
+        // it from becoming a bare "screenshot"
+        fs::create_dir_all("tests/assets").unwrap();
+        let path = PathBuf::from("tests/assets/naming_not_an_image.png");
+        fs::write(&path, b"not an image").unwrap();
+
+        let name = fallback_name(&path);
+        let captured_at = original_capture_time(&path).format("%Y-%m-%d %H.%M.%S");
+        assert_eq!(name, format!("screenshot {}", captured_at));
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_settings_validation() {
+        assert!(NamingSettings::default().validate().is_ok());
+
+        let mut settings = NamingSettings {
+            provider: NamingProvider::OpenAi,
+            ..NamingSettings::default()
+        };
+        assert!(settings.validate().is_ok());
+
+        settings.openai.base_url = "localhost:11434/v1".to_string();
+        assert!(settings.validate().is_err());
+
+        settings.openai.base_url = "http://localhost:11434/v1".to_string();
+        settings.openai.model = " ".to_string();
+        assert!(settings.validate().is_err());
+
+        settings.openai.model = "llava".to_string();
+        settings.openai.max_words = 0;
+        assert!(settings.validate().is_err());
+
+        settings.openai.max_words = 6;
+        settings.openai.system_prompt = String::new();
+        assert!(settings.validate().is_err());
+    }
+
+    #[test]
+    fn test_naming_error_serializes_with_kind() {
+        let error = NamingError::HttpStatus {
+            status: 503,
+            retry_after: None,
+        };
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            serde_json::json!({"kind": "httpStatus", "status": 503})
+        );
+        assert_eq!(error.kind(), "httpStatus");
+
+        assert_eq!(
+            serde_json::to_value(NamingError::HttpStatus {
+                status: 503,
+                retry_after: Some(30)
+            })
+            .unwrap(),
+            serde_json::json!({"kind": "httpStatus", "status": 503, "retryAfter": 30})
+        );
+        assert_eq!(
+            serde_json::to_value(NamingError::QuotaExceeded).unwrap(),
+            serde_json::json!({"kind": "quotaExceeded"})
+        );
+        assert_eq!(
+            serde_json::to_value(NamingError::rejected("nope")).unwrap(),
+            serde_json::json!({"kind": "rejected", "message": "nope"})
+        );
+    }
+
+    #[test]
+    fn test_retry_after_and_transient_errors() {
+        let path = screenshot("naming_retry_after.png");
+
+        let (url, _requests) = stub_server_with_headers(503, "Retry-After: 12\r\n", "{}");
+        let generator = HostedGenerator::new(&url, "device-1", "1.2.3");
+        let error = generator.generate(&path).unwrap_err();
+        assert_eq!(
+            error,
+            NamingError::HttpStatus {
+                status: 503,
+                retry_after: Some(12)
+            }
+        );
+        assert!(error.is_transient());
+
+        let now = Utc.with_ymd_and_hms(2026, 3, 1, 12, 0, 0).unwrap();
+        assert_eq!(parse_retry_after("120", now), Some(120));
+        assert_eq!(
+            parse_retry_after("Sun, 01 Mar 2026 12:01:30 GMT", now),
+            Some(90)
+        );
+        // A date already past means retry right away
+        assert_eq!(
+            parse_retry_after("Sun, 01 Mar 2026 11:00:00 GMT", now),
+            Some(0)
+        );
+        assert_eq!(parse_retry_after("soon", now), None);
+
+        let network = NamingError::Network {
+            message: "connection refused".to_string(),
+        };
+        assert!(network.is_transient());
+        assert!(!NamingError::QuotaExceeded.is_transient());
+        assert!(!NamingError::rejected("nope").is_transient());
+        assert!(!NamingError::Cancelled.is_transient());
+        assert!(!NamingError::HttpStatus {
+            status: 404,
+            retry_after: None
+        }
+        .is_transient());
+
+        fs::remove_file(&path).unwrap();
+    }
+}
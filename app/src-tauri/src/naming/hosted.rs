@@ -0,0 +1,88 @@
+use dotenvy_macro::dotenv;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use super::{clean_name, image_data_url, NameGenerator, NamingError};
+use crate::{http::client, user::UserDevice};
+
+#[derive(Serialize)]
+struct RequestPayload<'a> {
+    #[serde(rename = "base64Img")]
+    base_64_img: &'a str,
+    #[serde(rename = "deviceId")]
+    device_id: &'a str,
+    #[serde(rename = "appVersion")]
+    app_version: &'a str,
+}
+
+#[derive(Deserialize)]
+struct ApiResponse {
+    success: bool,
+    message: String,
+    data: Option<ScreenshotData>,
+}
+
+#[derive(Deserialize)]
+struct ScreenshotData {
+    #[serde(rename = "screenshotName")]
+    screenshot_name: String,
+}
+
+/// The SnapName naming service, billed against the device's plan.
+pub struct HostedGenerator {
+    url: String,
+    device_id: String,
+    app_version: String,
+}
+
+impl HostedGenerator {
+    pub fn new(url: &str, device_id: &str, app_version: &str) -> Self {
+        HostedGenerator {
+            url: url.to_string(),
+            device_id: device_id.to_string(),
+            app_version: app_version.to_string(),
+        }
+    }
+
+    /// Uses the service URL baked in at build time.
+    pub fn for_device(user_device: &UserDevice) -> Self {
+        Self::new(
+            dotenv!("GEN_SCREENSHOT_NAME_URL"),
+            &user_device.device_id,
+            &user_device.app_version,
+        )
+    }
+}
+
+impl NameGenerator for HostedGenerator {
+    fn label(&self) -> &'static str {
+        "hosted"
+    }
+
+    fn server_url(&self) -> Option<String> {
+        Some(self.url.clone())
+    }
+
+    fn generate(&self, path: &Path) -> Result<String, NamingError> {
+        let image_data_url = image_data_url(path).map_err(NamingError::file_unavailable)?;
+        let payload = RequestPayload {
+            base_64_img: &image_data_url,
+            device_id: &self.device_id,
+            app_version: &self.app_version,
+        };
+
+        let response = client().post(&self.url).json(&payload).send()?;
+        match response.status().as_u16() {
+            429 => return Err(NamingError::QuotaExceeded),
+            200..=299 => {}
+            _ => return Err(NamingError::from_response(&response)),
+        }
+        let api_response: ApiResponse = response.json()?;
+
+        match api_response.data {
+            Some(data) if api_response.success => clean_name(&data.screenshot_name, None)
+                .ok_or_else(|| NamingError::rejected("Server returned an empty name")),
+            _ => Err(NamingError::rejected(api_response.message)),
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use std::path::{Path, PathBuf};
+
+use super::{NameGenerator, NamingError};
+
+/// Detection and recognition models expected in the model folder, as
+/// published with the `ocrs` project.
+pub const DETECTION_MODEL: &str = "text-detection.rten";
+pub const RECOGNITION_MODEL: &str = "text-recognition.rten";
+
+/// Reads the text in a screenshot on the CPU and names it after that, so the
+/// image never leaves the machine. Needs the `local-ocr` feature.
+pub struct LocalGenerator {
+    model_dir: PathBuf,
+    max_words: usize,
+}
+
+impl LocalGenerator {
+    pub fn new(model_dir: &Path, max_words: usize) -> Self {
+        LocalGenerator {
+            model_dir: model_dir.to_path_buf(),
+            max_words,
+        }
+    }
+}
+
+impl NameGenerator for LocalGenerator {
+    fn label(&self) -> &'static str {
+        "local"
+    }
+
+    #[cfg(feature = "local-ocr")]
+    fn generate(&self, path: &Path) -> Result<String, NamingError> {
+        let text = ocr::read_text(&self.model_dir, path).map_err(NamingError::rejected)?;
+        name_from_text(&text, self.max_words)
+            .ok_or_else(|| NamingError::rejected("No text found in screenshot"))
+    }
+
+    #[cfg(not(feature = "local-ocr"))]
+    fn generate(&self, _path: &Path) -> Result<String, NamingError> {
+        Err(NamingError::rejected(format!(
+            "On-device naming isn't included in this build (models in {})",
+            self.model_dir.display()
+        )))
+    }
+}
+
+/// Picks a name out of recognized text: the first line with at least two
+/// words, or else the first words found, cut to `max_words`.
+pub fn name_from_text(text: &str, max_words: usize) -> Option<String> {
+    let lines: Vec<Vec<&str>> = text
+        .lines()
+        .map(|line| line.split_whitespace().filter_map(clean_word).collect())
+        .collect();
+
+    let words: Vec<&str> = match lines.iter().find(|words| words.len() >= 2) {
+        Some(words) => words.clone(),
+        None => lines.into_iter().flatten().collect(),
+    };
+    let name = words
+        .into_iter()
+        .take(max_words)
+        .collect::<Vec<_>>()
+        .join(" ");
+    (!name.is_empty()).then_some(name)
+}
+
+/// Trims punctuation OCR picks up around words and drops fragments that
+/// are mostly noise, like `|` or `—`.
+fn clean_word(word: &str) -> Option<&str> {
+    let word = word.trim_matches(|c: char| !c.is_alphanumeric());
+    let letters = word.chars().filter(|c| c.is_alphabetic()).count();
+    let digits = word.chars().filter(|c| c.is_numeric()).count();
+    (letters >= 2 || (digits > 0 && letters + digits == word.chars().count())).then_some(word)
+}
+
+#[cfg(feature = "local-ocr")]
+mod ocr {
+    use ocrs::{ImageSource, OcrEngine, OcrEngineParams};
+    use rten::Model;
+    use std::{
+        path::{Path, PathBuf},
+        sync::{Arc, Mutex},
+    };
+
+    use super::{DETECTION_MODEL, RECOGNITION_MODEL};
+
+    /// Loading the models takes a while, so the engine is kept for the
+    /// folder it was loaded from.
+    static ENGINE: Mutex<Option<(PathBuf, Arc<OcrEngine>)>> = Mutex::new(None);
+
+    fn engine(model_dir: &Path) -> Result<Arc<OcrEngine>, String> {
+        let mut cached = ENGINE.lock().unwrap();
+        if let Some((dir, engine)) = cached.as_ref() {
+            if dir == model_dir {
+                return Ok(engine.clone());
+            }
+        }
+
+        let load = |name: &str| {
+            let path = model_dir.join(name);
+            Model::load_file(&path)
+                .map_err(|e| format!("Failed to load model {}: {}", path.display(), e))
+        };
+        println!("🧠 Loading OCR models from {}", model_dir.display());
+        let engine = OcrEngine::new(OcrEngineParams {
+            detection_model: Some(load(DETECTION_MODEL)?),
+            recognition_model: Some(load(RECOGNITION_MODEL)?),
+            ..Default::default()
+        })
+        .map_err(|e| format!("Failed to start OCR engine: {}", e))?;
+
+        let engine = Arc::new(engine);
+        *cached = Some((model_dir.to_path_buf(), engine.clone()));
+        Ok(engine)
+    }
+
+    pub fn read_text(model_dir: &Path, path: &Path) -> Result<String, String> {
+        let engine = engine(model_dir)?;
+        let image = image::open(path)
+            .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?
+            .into_rgb8();
+        let source = ImageSource::from_bytes(image.as_raw(), image.dimensions())
+            .map_err(|e| format!("Unsupported image layout: {}", e))?;
+        let input = engine
+            .prepare_input(source)
+            .map_err(|e| format!("Failed to prepare image: {}", e))?;
+        engine
+            .get_text(&input)
+            .map_err(|e| format!("Failed to read text: {}", e))
+    }
+}
@@ -0,0 +1,200 @@
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone};
+use regex::Regex;
+use std::{collections::HashMap, fs, path::Path, sync::OnceLock};
+
+use super::{NameGenerator, NamingError};
+use crate::template::capture_time;
+
+/// Names screenshots from what's on disk, without any network. The same file
+/// always gets the same name.
+pub struct OfflineGenerator;
+
+impl NameGenerator for OfflineGenerator {
+    fn label(&self) -> &'static str {
+        "offline"
+    }
+
+    fn generate(&self, path: &Path) -> Result<String, NamingError> {
+        Ok(fallback_name(path))
+    }
+}
+
+/// A name built only from the file: its title if the PNG carries one,
+/// otherwise the main color and size, always followed by the capture time so
+/// two screenshots rarely share a name, e.g. `blue 1440x900 2025-06-12 10.22.33`.
+pub fn fallback_name(path: &Path) -> String {
+    let captured_at = original_capture_time(path)
+        .format("%Y-%m-%d %H.%M.%S")
+        .to_string();
+
+    if let Some(title) = png_title(path) {
+        return format!("{} {}", title, captured_at);
+    }
+
+    let mut parts = Vec::new();
+    if let Some(color) = dominant_color(path) {
+        parts.push(color.to_string());
+    }
+    match image::image_dimensions(path) {
+        Ok((width, height)) => parts.push(format!("{}x{}", width, height)),
+        Err(_) => parts.push("screenshot".to_string()),
+    }
+    parts.push(captured_at);
+    parts.join(" ")
+}
+
+/// Capture time from the name the OS gave the screenshot, which survives
+/// copies that reset file times, else from the file itself.
+pub fn original_capture_time(path: &Path) -> DateTime<Local> {
+    path.file_stem()
+        .and_then(|stem| parse_capture_time(&stem.to_string_lossy()))
+        .and_then(|time| Local.from_local_datetime(&time).earliest())
+        .unwrap_or_else(|| capture_time(path))
+}
+
+/// Reads the date and time out of names like `Screenshot 2025-06-12 at
+/// 10.22.33`, `Screen Shot 2020-01-01 at 1.02.03 PM`, `Screenshot from
+/// 2025-06-12 10-22-33` or `Screenshot_20250612_102233`.
+pub fn parse_capture_time(name: &str) -> Option<NaiveDateTime> {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    let pattern = PATTERN.get_or_init(|| {
+        Regex::new(
+            r"(?i)(\d{4})-?(\d{2})-?(\d{2})(?:\s+at\s+|[\s_T-]+)(\d{1,2})[.:-]?(\d{2})[.:-]?(\d{2})(?:\s*([AP])\.?M\.?)?",
+        )
+        .unwrap()
+    });
+    let caps = pattern.captures(name)?;
+    let number = |i: usize| caps[i].parse::<u32>().ok();
+
+    let mut hour = number(4)?;
+    match caps.get(7).map(|m| m.as_str().to_ascii_uppercase()) {
+        Some(half) if half == "P" && hour < 12 => hour += 12,
+        Some(half) if half == "A" && hour == 12 => hour = 0,
+        _ => {}
+    }
+    NaiveDate::from_ymd_opt(number(1)? as i32, number(2)?, number(3)?)?.and_hms_opt(
+        hour,
+        number(5)?,
+        number(6)?,
+    )
+}
+
+/// `Title`, or failing that `Description`, from the PNG's text chunks.
+pub fn png_title(path: &Path) -> Option<String> {
+    let data = fs::read(path).ok()?;
+    let chunks = data.strip_prefix(b"\x89PNG\r\n\x1a\n")?;
+    let texts = png_text_chunks(chunks);
+
+    ["Title", "Description"].iter().find_map(|key| {
+        texts
+            .iter()
+            .find(|(keyword, _)| keyword == key)
+            .map(|(_, text)| text.split_whitespace().collect::<Vec<_>>().join(" "))
+            .filter(|text| !text.is_empty())
+    })
+}
+
+/// Keyword and text of every uncompressed `tEXt` and `iTXt` chunk.
+fn png_text_chunks(mut chunks: &[u8]) -> Vec<(String, String)> {
+    let mut texts = Vec::new();
+    while chunks.len() >= 12 {
+        let length = u32::from_be_bytes([chunks[0], chunks[1], chunks[2], chunks[3]]) as usize;
+        let kind = &chunks[4..8];
+        let Some(body) = chunks.get(8..8 + length) else {
+            break;
+        };
+
+        match kind {
+            b"tEXt" => {
+                if let Some((keyword, text)) = split_nul(body) {
+                    // Latin-1, which maps byte for byte onto the first code points
+                    let text = text.iter().map(|&b| b as char).collect();
+                    texts.push((String::from_utf8_lossy(keyword).to_string(), text));
+                }
+            }
+            b"iTXt" => {
+                if let Some(text) = itxt_text(body) {
+                    texts.push(text);
+                }
+            }
+            b"IDAT" | b"IEND" => break,
+            _ => {}
+        }
+        // Length, type, data and CRC
+        chunks = chunks.get(12 + length..).unwrap_or_default();
+    }
+    texts
+}
+
+fn itxt_text(body: &[u8]) -> Option<(String, String)> {
+    let (keyword, rest) = split_nul(body)?;
+    let (&compressed, rest) = rest.split_first()?;
+    if compressed != 0 {
+        return None;
+    }
+    // Compression method, then language tag and translated keyword
+    let (_, rest) = rest.split_first()?;
+    let (_, rest) = split_nul(rest)?;
+    let (_, text) = split_nul(rest)?;
+    Some((
+        String::from_utf8_lossy(keyword).to_string(),
+        String::from_utf8_lossy(text).to_string(),
+    ))
+}
+
+fn split_nul(bytes: &[u8]) -> Option<(&[u8], &[u8])> {
+    let index = bytes.iter().position(|&b| b == 0)?;
+    Some((&bytes[..index], &bytes[index + 1..]))
+}
+
+/// The color name covering most of a small thumbnail of the image.
+pub fn dominant_color(path: &Path) -> Option<&'static str> {
+    let thumbnail = image::open(path).ok()?.thumbnail(32, 32).into_rgb8();
+    let mut counts: HashMap<&'static str, usize> = HashMap::new();
+    for pixel in thumbnail.pixels() {
+        *counts.entry(color_name(pixel.0)).or_default() += 1;
+    }
+    // Ties go to the name that sorts first, so the result is stable
+    counts
+        .into_iter()
+        .max_by(|a, b| a.1.cmp(&b.1).then(b.0.cmp(a.0)))
+        .map(|(name, _)| name)
+}
+
+pub fn color_name([r, g, b]: [u8; 3]) -> &'static str {
+    let max = r.max(g).max(b) as f32;
+    let min = r.min(g).min(b) as f32;
+    let chroma = max - min;
+
+    if max < 40.0 {
+        return "black";
+    }
+    if chroma < 0.15 * max || chroma < 20.0 {
+        return match max {
+            m if m > 215.0 => "white",
+            m if m < 70.0 => "black",
+            _ => "gray",
+        };
+    }
+
+    let (r, g, b) = (r as f32, g as f32, b as f32);
+    let hue = if max == r {
+        60.0 * ((g - b) / chroma).rem_euclid(6.0)
+    } else if max == g {
+        60.0 * ((b - r) / chroma + 2.0)
+    } else {
+        60.0 * ((r - g) / chroma + 4.0)
+    };
+
+    match hue {
+        h if !(15.0..345.0).contains(&h) => "red",
+        h if h < 45.0 && max < 160.0 => "brown",
+        h if h < 45.0 => "orange",
+        h if h < 70.0 => "yellow",
+        h if h < 165.0 => "green",
+        h if h < 200.0 => "teal",
+        h if h < 255.0 => "blue",
+        h if h < 290.0 => "purple",
+        _ => "pink",
+    }
+}
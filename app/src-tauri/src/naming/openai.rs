@@ -0,0 +1,91 @@
+use serde::Deserialize;
+use serde_json::json;
+use std::path::Path;
+
+use super::{clean_name, image_data_url, NameGenerator, NamingError, OpenAiSettings};
+use crate::http::client;
+
+#[derive(Deserialize)]
+struct ChatResponse {
+    choices: Vec<Choice>,
+}
+
+#[derive(Deserialize)]
+struct Choice {
+    message: Message,
+}
+
+#[derive(Deserialize)]
+struct Message {
+    content: Option<String>,
+}
+
+/// A vision model behind the OpenAI chat completions API: OpenAI itself, a
+/// company gateway, or a local Ollama or vLLM server.
+pub struct OpenAiGenerator {
+    settings: OpenAiSettings,
+}
+
+impl OpenAiGenerator {
+    pub fn new(settings: &OpenAiSettings) -> Self {
+        OpenAiGenerator {
+            settings: settings.clone(),
+        }
+    }
+
+    fn endpoint(&self) -> String {
+        format!(
+            "{}/chat/completions",
+            self.settings.base_url.trim().trim_end_matches('/')
+        )
+    }
+}
+
+impl NameGenerator for OpenAiGenerator {
+    fn label(&self) -> &'static str {
+        "openai"
+    }
+
+    fn server_url(&self) -> Option<String> {
+        Some(self.endpoint())
+    }
+
+    fn generate(&self, path: &Path) -> Result<String, NamingError> {
+        let image_data_url = image_data_url(path).map_err(NamingError::file_unavailable)?;
+        let payload = json!({
+            "model": self.settings.model,
+            // Words run to a few tokens each; leaves room for a stray quote or label
+            "max_tokens": self.settings.max_words * 4 + 16,
+            "messages": [
+                { "role": "system", "content": self.settings.prompt() },
+                {
+                    "role": "user",
+                    "content": [
+                        { "type": "text", "text": "Name this screenshot." },
+                        { "type": "image_url", "image_url": { "url": image_data_url } }
+                    ]
+                }
+            ]
+        });
+
+        let mut request = client().post(self.endpoint()).json(&payload);
+        if !self.settings.api_key.is_empty() {
+            request = request.bearer_auth(&self.settings.api_key);
+        }
+        let response = request.send()?;
+        if !response.status().is_success() {
+            // Not the SnapName quota, so a 429 never asks the user to upgrade
+            return Err(NamingError::from_response(&response));
+        }
+        let chat: ChatResponse = response.json()?;
+        let content = chat
+            .choices
+            .into_iter()
+            .next()
+            .and_then(|choice| choice.message.content)
+            .unwrap_or_default();
+
+        clean_name(&content, Some(self.settings.max_words))
+            .ok_or_else(|| NamingError::rejected("Naming server returned an empty name"))
+    }
+}
@@ -0,0 +1,287 @@
+use chrono::{DateTime, Utc};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use std::{
+    net::{TcpStream, ToSocketAddrs},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::{
+    naming::{generator_for, NamingError},
+    pause::current_pause_status,
+    state::AppState,
+    store::{data_file, load_json, save_json},
+};
+
+const OFFLINE_QUEUE_FILE: &str = "offline_queue.json";
+
+/// How often the retry thread looks for a connection.
+const CHECK_INTERVAL: Duration = Duration::from_secs(15);
+
+/// While the server is reachable but still failing, e.g. a long outage with
+/// 5xx errors, screenshots are retried at most this often.
+const RETRY_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// A screenshot whose name couldn't be fetched because of the network.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OfflineScreenshot {
+    pub path: PathBuf,
+    pub queued_at: DateTime<Utc>,
+    /// Times naming failed, counting retries from this queue
+    pub attempts: u32,
+    pub last_error: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OfflineData {
+    items: Vec<OfflineScreenshot>,
+}
+
+/// Screenshots waiting for the network to come back, saved as
+/// `offline_queue.json` in the app data dir so they survive a restart.
+#[derive(Clone)]
+pub struct OfflineQueue {
+    data: Arc<Mutex<OfflineData>>,
+    file: Option<PathBuf>,
+}
+
+impl OfflineQueue {
+    pub fn load(app_handle: &AppHandle) -> Self {
+        Self::open(data_file(app_handle, OFFLINE_QUEUE_FILE))
+    }
+
+    /// Reads the queue from `file`; `None` keeps it in memory only.
+    pub fn open(file: Option<PathBuf>) -> Self {
+        let data = match &file {
+            Some(path) => load_json(path),
+            None => OfflineData::default(),
+        };
+        OfflineQueue {
+            data: Arc::new(Mutex::new(data)),
+            file,
+        }
+    }
+
+    pub fn items(&self) -> Vec<OfflineScreenshot> {
+        self.data.lock().unwrap().items.clone()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.lock().unwrap().items.is_empty()
+    }
+
+    /// Queues `path`, or counts another failed attempt if it's already queued.
+    pub fn add(&self, path: &Path, error: &NamingError) -> OfflineScreenshot {
+        let mut data = self.data.lock().unwrap();
+        let item = match data.items.iter_mut().find(|item| item.path == path) {
+            Some(item) => {
+                item.attempts += 1;
+                item.last_error = error.to_string();
+                item.clone()
+            }
+            None => {
+                let item = OfflineScreenshot {
+                    path: path.to_path_buf(),
+                    queued_at: Utc::now(),
+                    attempts: 1,
+                    last_error: error.to_string(),
+                };
+                data.items.push(item.clone());
+                item
+            }
+        };
+        self.save(&data);
+        item
+    }
+
+    /// Drops `path` once it has been dealt with. Returns whether it was queued.
+    pub fn remove(&self, path: &Path) -> bool {
+        let mut data = self.data.lock().unwrap();
+        let before = data.items.len();
+        data.items.retain(|item| item.path != path);
+        let removed = data.items.len() != before;
+        if removed {
+            self.save(&data);
+        }
+        removed
+    }
+
+    /// Drops screenshots that were deleted or moved away in the meantime.
+    pub fn prune_missing(&self) -> Vec<OfflineScreenshot> {
+        let mut data = self.data.lock().unwrap();
+        let (kept, missing): (Vec<_>, Vec<_>) =
+            data.items.drain(..).partition(|item| item.path.exists());
+        data.items = kept;
+        if !missing.is_empty() {
+            self.save(&data);
+        }
+        missing
+    }
+
+    // Saved under the lock so concurrent workers don't interleave writes
+    fn save(&self, data: &OfflineData) {
+        if let Some(file) = &self.file {
+            if let Err(e) = save_json(file, data) {
+                eprintln!("❌ Failed to save offline queue: {}", e);
+            }
+        }
+    }
+}
+
+/// Whether a TCP connection to the host of `url` can be opened.
+pub fn is_reachable(url: &str) -> bool {
+    let Ok(url) = Url::parse(url) else {
+        return false;
+    };
+    let (Some(host), Some(port)) = (url.host_str(), url.port_or_known_default()) else {
+        return false;
+    };
+    let Ok(mut addrs) = (host, port).to_socket_addrs() else {
+        // DNS failing is the usual sign of being offline
+        return false;
+    };
+    addrs.any(|addr| TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT).is_ok())
+}
+
+/// Puts offline screenshots back on the job queue once the naming server can
+/// be reached again. Runs for the life of the app.
+pub fn spawn_offline_retry(app_handle: AppHandle) {
+    thread::spawn(move || {
+        // Unknown at startup, so anything left from last time is retried as
+        // soon as the server answers
+        let mut was_online = false;
+        let mut last_retry: Option<Instant> = None;
+        loop {
+            thread::sleep(CHECK_INTERVAL);
+
+            let state = app_handle.state::<AppState>();
+            if state.offline.is_empty() || current_pause_status(&state).paused {
+                continue;
+            }
+
+            let naming = state.settings.lock().unwrap().naming.clone();
+            let generator = generator_for(&naming, &state.user_device, &app_handle);
+            let online = generator.server_url().is_none_or(|url| is_reachable(&url));
+
+            let due = last_retry.is_none_or(|at| at.elapsed() >= RETRY_INTERVAL);
+            if online && (!was_online || due) {
+                retry_offline(&app_handle, &state);
+                last_retry = Some(Instant::now());
+            }
+            was_online = online;
+        }
+    });
+}
+
+/// Queues every offline screenshot that still exists. They stay in the
+/// offline queue until the pipeline is done with them.
+pub fn retry_offline(app_handle: &AppHandle, state: &AppState) {
+    for item in state.offline.prune_missing() {
+        println!(
+            "🗑️ Dropped missing offline screenshot: {}",
+            item.path.display()
+        );
+    }
+    let items = state.offline.items();
+    if !items.is_empty() {
+        println!("🌐 Back online, naming {} screenshot(s)", items.len());
+    }
+    for item in items {
+        state.queue.enqueue(item.path);
+    }
+    emit_offline(app_handle, state);
+}
+
+pub fn emit_offline(app_handle: &AppHandle, state: &AppState) {
+    if let Err(e) = app_handle.emit_to("main", "offline-queue-updated", state.offline.items()) {
+        eprintln!("❌ Failed to emit event: {:?}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    fn setup(dir: &str) -> PathBuf {
+        let dir = PathBuf::from("tests/assets").join(dir);
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn offline() -> NamingError {
+        NamingError::Network {
+            message: "dns error".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_offline_queue_survives_restart() {
+        let dir = setup("offline_restart");
+        let file = dir.join("offline_queue.json");
+        let screenshot = dir.join("Screenshot 1.png");
+        fs::write(&screenshot, b"png").unwrap();
+
+        let queue = OfflineQueue::open(Some(file.clone()));
+        queue.add(&screenshot, &offline());
+
+        let reopened = OfflineQueue::open(Some(file));
+        let items = reopened.items();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].path, screenshot);
+        assert_eq!(items[0].attempts, 1);
+        assert_eq!(items[0].last_error, offline().to_string());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_offline_queue_counts_attempts_per_file() {
+        let queue = OfflineQueue::open(None);
+        let path = Path::new("/tmp/Screenshot 2.png");
+
+        queue.add(path, &offline());
+        let busy = NamingError::HttpStatus {
+            status: 503,
+            retry_after: None,
+        };
+        let item = queue.add(path, &busy);
+
+        assert_eq!(item.attempts, 2);
+        assert_eq!(item.last_error, busy.to_string());
+        assert_eq!(queue.items().len(), 1);
+    }
+
+    #[test]
+    fn test_offline_queue_remove_and_prune() {
+        let dir = setup("offline_prune");
+        let kept = dir.join("kept.png");
+        fs::write(&kept, b"png").unwrap();
+        let deleted = dir.join("deleted.png");
+
+        let queue = OfflineQueue::open(None);
+        queue.add(&kept, &offline());
+        queue.add(&deleted, &offline());
+
+        let missing = queue.prune_missing();
+        assert_eq!(missing.len(), 1);
+        assert_eq!(missing[0].path, deleted);
+        assert_eq!(queue.items().len(), 1);
+
+        assert!(queue.remove(&kept));
+        assert!(!queue.remove(&kept));
+        assert!(queue.is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
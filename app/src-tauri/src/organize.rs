@@ -0,0 +1,151 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::{
+    sanitize::{detect_filesystem, sanitize_file_name},
+    template::{render_template, validate_template, CaseStyle, TemplateContext},
+};
+
+/// Folder created inside the watched folder when no destination is set.
+const DEFAULT_ROOT_NAME: &str = "Screenshots";
+
+/// Moves renamed screenshots into a dated tree instead of leaving them where
+/// they were taken, e.g. `~/Desktop/Screenshots/2026/10/`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct OrganizeSettings {
+    pub enabled: bool,
+    /// Absolute folder the tree starts in; empty means a `Screenshots` folder
+    /// next to the original file
+    pub root: String,
+    /// `/`-separated folder names, each a name template like `{date:%Y}`
+    pub subfolders: String,
+}
+
+impl Default for OrganizeSettings {
+    fn default() -> Self {
+        OrganizeSettings {
+            enabled: false,
+            root: String::new(),
+            subfolders: "{date:%Y}/{date:%m}".to_string(),
+        }
+    }
+}
+
+impl OrganizeSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.root.is_empty() && !Path::new(&self.root).is_absolute() {
+            return Err(format!(
+                "Organize folder must be an absolute path: {}",
+                self.root
+            ));
+        }
+        for segment in subfolder_segments(&self.subfolders) {
+            validate_template(segment)?;
+        }
+        Ok(())
+    }
+
+    /// Folder the screenshot at `path` should end up in.
+    pub fn destination_dir(
+        &self,
+        path: &Path,
+        context: &TemplateContext,
+    ) -> Result<PathBuf, String> {
+        let mut dir = if self.root.is_empty() {
+            path.parent()
+                .ok_or("Can't determine parent directory")?
+                .join(DEFAULT_ROOT_NAME)
+        } else {
+            PathBuf::from(&self.root)
+        };
+
+        // Each level is sanitized on its own so a token can't add levels or
+        // climb out of the root
+        let fs = detect_filesystem(dir.ancestors().find(|d| d.exists()).unwrap_or(&dir));
+        for segment in subfolder_segments(&self.subfolders) {
+            let name = render_template(segment, CaseStyle::Original, context)?;
+            dir.push(sanitize_file_name(&name, "", fs)?);
+        }
+        Ok(dir)
+    }
+}
+
+fn subfolder_segments(subfolders: &str) -> impl Iterator<Item = &str> {
+    subfolders.split('/').filter(|s| !s.trim().is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::template::TemplateContext;
+    use chrono::{Local, TimeZone};
+    use std::path::{Path, PathBuf};
+
+    fn context() -> TemplateContext {
+        TemplateContext {
+            name: "login page error".to_string(),
+            original: "Screenshot 2026-10-03 at 10.22.33".to_string(),
+            folder: "Desktop".to_string(),
+            captured_at: Local.with_ymd_and_hms(2026, 10, 3, 10, 22, 33).unwrap(),
+            seq: 1,
+            host: "studio-mac".to_string(),
+        }
+    }
+
+    fn organize(root: &str, subfolders: &str) -> OrganizeSettings {
+        OrganizeSettings {
+            enabled: true,
+            root: root.to_string(),
+            subfolders: subfolders.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_default_tree_next_to_screenshot() {
+        let path = Path::new("/Users/me/Desktop/Screenshot 2026-10-03 at 10.22.33.png");
+        let dir = OrganizeSettings::default()
+            .destination_dir(path, &context())
+            .unwrap();
+        assert_eq!(dir, PathBuf::from("/Users/me/Desktop/Screenshots/2026/10"));
+    }
+
+    #[test]
+    fn test_custom_root_and_category_folders() {
+        let path = Path::new("/Users/me/Desktop/shot.png");
+        let dir = organize("/Users/me/Pictures", "{folder}/{date:%Y-%m}")
+            .destination_dir(path, &context())
+            .unwrap();
+        assert_eq!(dir, PathBuf::from("/Users/me/Pictures/Desktop/2026-10"));
+    }
+
+    #[test]
+    fn test_empty_subfolders_use_root() {
+        let path = Path::new("/Users/me/Desktop/shot.png");
+        let dir = organize("/Users/me/Pictures", "")
+            .destination_dir(path, &context())
+            .unwrap();
+        assert_eq!(dir, PathBuf::from("/Users/me/Pictures"));
+    }
+
+    #[test]
+    fn test_tokens_cannot_escape_root() {
+        let path = Path::new("/Users/me/Desktop/shot.png");
+        let mut context = context();
+        context.folder = "../../etc".to_string();
+        let dir = organize("/Users/me/Pictures", "{folder}")
+            .destination_dir(path, &context)
+            .unwrap();
+        assert_eq!(dir, PathBuf::from("/Users/me/Pictures/etc"));
+    }
+
+    #[test]
+    fn test_validation() {
+        assert!(OrganizeSettings::default().validate().is_ok());
+        assert!(organize("relative/path", "{date:%Y}").validate().is_err());
+        assert!(organize("/Users/me/Pictures", "{nope}").validate().is_err());
+        assert!(organize("/Users/me/Pictures", "{date:%Y}//{date:%m}")
+            .validate()
+            .is_ok());
+    }
+}
@@ -0,0 +1,523 @@
+use chrono::{DateTime, Datelike, Duration, Local, NaiveTime, TimeZone, Utc, Weekday};
+use serde::{Deserialize, Serialize};
+use std::{
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+use tauri::{AppHandle, Emitter};
+
+use crate::{
+    queue::JobQueue,
+    state::AppState,
+    store::{data_file, load_json, save_json},
+};
+
+const DEFERRED_FILE: &str = "deferred_screenshots.json";
+
+/// A recurring window during which the watcher pauses itself, e.g. weekday
+/// mornings while presenting. Windows may cross midnight.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuietHours {
+    pub enabled: bool,
+    pub days: Vec<Weekday>,
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl QuietHours {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.days.is_empty() {
+            return Err("Quiet hours need at least one day".to_string());
+        }
+        if self.start == self.end {
+            return Err("Quiet hours start and end can't be the same time".to_string());
+        }
+        Ok(())
+    }
+
+    /// End of the window containing `now`, if any. A window belongs to the
+    /// day it starts on, so Friday 22:00–07:00 also covers Saturday morning.
+    pub fn active_until(&self, now: DateTime<Local>) -> Option<DateTime<Local>> {
+        if !self.enabled {
+            return None;
+        }
+
+        let today = now.date_naive();
+        for start_day in [today - Duration::days(1), today] {
+            if !self.days.contains(&start_day.weekday()) {
+                continue;
+            }
+            let end_day = if self.end > self.start {
+                start_day
+            } else {
+                start_day + Duration::days(1)
+            };
+            // Skip windows that fall into a DST gap
+            let (Some(start), Some(end)) = (
+                Local
+                    .from_local_datetime(&start_day.and_time(self.start))
+                    .earliest(),
+                Local
+                    .from_local_datetime(&end_day.and_time(self.end))
+                    .earliest(),
+            ) else {
+                continue;
+            };
+            if start <= now && now < end {
+                return Some(end);
+            }
+        }
+        None
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PauseReason {
+    /// Paused from the UI until resumed
+    Manual,
+    /// "Pause for N minutes" or "pause until"
+    Timer,
+    /// Inside a quiet-hours window
+    Schedule,
+}
+
+/// What the UI shows: whether the watcher is paused, why, and until when.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PauseStatus {
+    pub paused: bool,
+    pub reason: Option<PauseReason>,
+    /// `None` while paused means until resumed by hand
+    pub until: Option<DateTime<Utc>>,
+}
+
+impl PauseStatus {
+    pub fn running() -> Self {
+        PauseStatus {
+            paused: false,
+            reason: None,
+            until: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum ManualPause {
+    UntilResumed,
+    Until(DateTime<Utc>),
+}
+
+/// Pause requests made from the UI. Quiet hours are evaluated on top of this.
+#[derive(Debug, Clone, Default)]
+pub struct PauseState {
+    manual: Option<ManualPause>,
+    /// Resuming during quiet hours skips the rest of that window
+    schedule_skipped_until: Option<DateTime<Utc>>,
+}
+
+impl PauseState {
+    /// Pauses until `until`, or until resumed by hand when `None`.
+    pub fn pause(&mut self, until: Option<DateTime<Utc>>) {
+        self.manual = Some(match until {
+            Some(until) => ManualPause::Until(until),
+            None => ManualPause::UntilResumed,
+        });
+    }
+
+    pub fn resume(&mut self, quiet_hours: &[QuietHours]) {
+        self.manual = None;
+        self.schedule_skipped_until = schedule_end(quiet_hours, Local::now());
+    }
+
+    /// Current status; timers that have run out are cleared on the way.
+    pub fn status(&mut self, quiet_hours: &[QuietHours]) -> PauseStatus {
+        let now = Utc::now();
+
+        match self.manual {
+            Some(ManualPause::UntilResumed) => {
+                return PauseStatus {
+                    paused: true,
+                    reason: Some(PauseReason::Manual),
+                    until: None,
+                }
+            }
+            Some(ManualPause::Until(until)) if until > now => {
+                return PauseStatus {
+                    paused: true,
+                    reason: Some(PauseReason::Timer),
+                    until: Some(until),
+                }
+            }
+            Some(ManualPause::Until(_)) => self.manual = None,
+            None => {}
+        }
+
+        if self.schedule_skipped_until.is_some_and(|until| until > now) {
+            return PauseStatus::running();
+        }
+        self.schedule_skipped_until = None;
+
+        match schedule_end(quiet_hours, now.with_timezone(&Local)) {
+            Some(until) => PauseStatus {
+                paused: true,
+                reason: Some(PauseReason::Schedule),
+                until: Some(until),
+            },
+            None => PauseStatus::running(),
+        }
+    }
+}
+
+/// Latest end among the quiet-hours windows active at `now`.
+fn schedule_end(quiet_hours: &[QuietHours], now: DateTime<Local>) -> Option<DateTime<Utc>> {
+    quiet_hours
+        .iter()
+        .filter_map(|q| q.active_until(now))
+        .max()
+        .map(|end| end.with_timezone(&Utc))
+}
+
+pub fn current_pause_status(state: &AppState) -> PauseStatus {
+    let quiet_hours = state.settings.lock().unwrap().quiet_hours.clone();
+    state.paused.lock().unwrap().status(&quiet_hours)
+}
+
+pub fn emit_pause_status(app_handle: &AppHandle, status: &PauseStatus) {
+    if let Err(e) = app_handle.emit_to("main", "pause-changed", status) {
+        eprintln!("❌ Failed to emit event: {:?}", e);
+    }
+}
+
+/// What happens to screenshots detected while the watcher is paused.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PauseMode {
+    /// Ignore them, as if the app wasn't running
+    #[default]
+    Skip,
+    /// Hold them in a pending list and name them on resume
+    Defer,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeferredScreenshot {
+    pub path: PathBuf,
+    pub detected_at: DateTime<Utc>,
+}
+
+/// Screenshots seen while paused in `Defer` mode, saved as
+/// `deferred_screenshots.json` in the app data dir so quitting while paused
+/// doesn't lose them.
+#[derive(Clone)]
+pub struct DeferredScreenshots {
+    items: Arc<Mutex<Vec<DeferredScreenshot>>>,
+    file: Option<PathBuf>,
+}
+
+impl DeferredScreenshots {
+    pub fn load(app_handle: &AppHandle) -> Self {
+        Self::open(data_file(app_handle, DEFERRED_FILE))
+    }
+
+    /// Reads the list from `file`; `None` keeps it in memory only.
+    pub fn open(file: Option<PathBuf>) -> Self {
+        let items = match &file {
+            Some(path) => load_json(path),
+            None => Vec::new(),
+        };
+        DeferredScreenshots {
+            items: Arc::new(Mutex::new(items)),
+            file,
+        }
+    }
+
+    pub fn items(&self) -> Vec<DeferredScreenshot> {
+        self.items.lock().unwrap().clone()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.lock().unwrap().is_empty()
+    }
+
+    /// Returns whether `path` was added, i.e. wasn't deferred already.
+    pub fn add(&self, path: PathBuf) -> bool {
+        let mut items = self.items.lock().unwrap();
+        if items.iter().any(|d| d.path == path) {
+            return false;
+        }
+        items.push(DeferredScreenshot {
+            path,
+            detected_at: Utc::now(),
+        });
+        self.save(&items);
+        true
+    }
+
+    /// Queues every deferred screenshot that still exists and empties the
+    /// list. Returns how many were queued.
+    pub fn flush(&self, queue: &JobQueue) -> usize {
+        let mut items = self.items.lock().unwrap();
+        let mut queued = 0;
+        for item in items.drain(..) {
+            if item.path.exists() && queue.enqueue(item.path).is_some() {
+                queued += 1;
+            }
+        }
+        self.save(&items);
+        queued
+    }
+
+    /// Drops the given screenshots, or all of them when `paths` is `None`.
+    /// The files themselves are left untouched.
+    pub fn discard(&self, paths: Option<&[PathBuf]>) {
+        let mut items = self.items.lock().unwrap();
+        match paths {
+            Some(paths) => items.retain(|d| !paths.contains(&d.path)),
+            None => items.clear(),
+        }
+        self.save(&items);
+    }
+
+    // Saved under the lock so the watcher and commands don't interleave writes
+    fn save(&self, items: &[DeferredScreenshot]) {
+        if let Some(file) = &self.file {
+            if let Err(e) = save_json(file, &items) {
+                eprintln!("❌ Failed to save deferred screenshots: {}", e);
+            }
+        }
+    }
+}
+
+/// Remembers a screenshot seen while paused in `Defer` mode.
+pub fn defer_screenshot(app_handle: &AppHandle, state: &AppState, path: PathBuf) {
+    let display = path.display().to_string();
+    if state.deferred.add(path) {
+        println!("⏸️ Deferred screenshot: {}", display);
+        emit_deferred(app_handle, state);
+    }
+}
+
+/// Queues every deferred screenshot that still exists.
+pub fn flush_deferred(app_handle: &AppHandle, state: &AppState) {
+    if state.deferred.is_empty() {
+        return;
+    }
+    let queued = state.deferred.flush(&state.queue);
+    println!("▶️ Naming {} deferred screenshot(s)", queued);
+    emit_deferred(app_handle, state);
+}
+
+/// Drops the given deferred screenshots, or all of them when `paths` is `None`.
+/// The files themselves are left untouched.
+pub fn discard_deferred(app_handle: &AppHandle, state: &AppState, paths: Option<Vec<PathBuf>>) {
+    state.deferred.discard(paths.as_deref());
+    emit_deferred(app_handle, state);
+}
+
+fn emit_deferred(app_handle: &AppHandle, state: &AppState) {
+    if let Err(e) = app_handle.emit_to("main", "deferred-updated", state.deferred.items()) {
+        eprintln!("❌ Failed to emit event: {:?}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Local, NaiveTime, TimeZone, Utc, Weekday};
+
+    fn quiet_hours(days: &[Weekday], start: &str, end: &str) -> QuietHours {
+        QuietHours {
+            enabled: true,
+            days: days.to_vec(),
+            start: NaiveTime::parse_from_str(start, "%H:%M").unwrap(),
+            end: NaiveTime::parse_from_str(end, "%H:%M").unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_quiet_hours_same_day() {
+        // 2025-06-12 is a Thursday
+        let window = quiet_hours(&[Weekday::Thu], "09:00", "10:30");
+
+        let inside = Local.with_ymd_and_hms(2025, 6, 12, 9, 15, 0).unwrap();
+        let end = Local.with_ymd_and_hms(2025, 6, 12, 10, 30, 0).unwrap();
+        assert_eq!(window.active_until(inside), Some(end));
+
+        let before = Local.with_ymd_and_hms(2025, 6, 12, 8, 59, 0).unwrap();
+        assert_eq!(window.active_until(before), None);
+        assert_eq!(window.active_until(end), None);
+
+        let other_day = Local.with_ymd_and_hms(2025, 6, 13, 9, 15, 0).unwrap();
+        assert_eq!(window.active_until(other_day), None);
+    }
+
+    #[test]
+    fn test_quiet_hours_across_midnight() {
+        // Friday night into Saturday morning
+        let window = quiet_hours(&[Weekday::Fri], "22:00", "07:00");
+
+        let friday_night = Local.with_ymd_and_hms(2025, 6, 13, 23, 0, 0).unwrap();
+        let saturday_morning = Local.with_ymd_and_hms(2025, 6, 14, 6, 0, 0).unwrap();
+        let end = Local.with_ymd_and_hms(2025, 6, 14, 7, 0, 0).unwrap();
+        assert_eq!(window.active_until(friday_night), Some(end));
+        assert_eq!(window.active_until(saturday_morning), Some(end));
+
+        // Friday morning belongs to Thursday's window, which isn't enabled
+        let friday_morning = Local.with_ymd_and_hms(2025, 6, 13, 6, 0, 0).unwrap();
+        assert_eq!(window.active_until(friday_morning), None);
+    }
+
+    #[test]
+    fn test_quiet_hours_disabled() {
+        let mut window = quiet_hours(&[Weekday::Thu], "00:00", "23:59");
+        window.enabled = false;
+        let now = Local.with_ymd_and_hms(2025, 6, 12, 12, 0, 0).unwrap();
+        assert_eq!(window.active_until(now), None);
+    }
+
+    #[test]
+    fn test_quiet_hours_validation() {
+        assert!(quiet_hours(&[Weekday::Mon], "09:00", "17:00")
+            .validate()
+            .is_ok());
+        assert!(quiet_hours(&[], "09:00", "17:00").validate().is_err());
+        assert!(quiet_hours(&[Weekday::Mon], "09:00", "09:00")
+            .validate()
+            .is_err());
+    }
+
+    #[test]
+    fn test_manual_pause_and_resume() {
+        let mut state = PauseState::default();
+        assert!(!state.status(&[]).paused);
+
+        state.pause(None);
+        let status = state.status(&[]);
+        assert!(status.paused);
+        assert_eq!(status.reason, Some(PauseReason::Manual));
+        assert_eq!(status.until, None);
+
+        state.resume(&[]);
+        assert!(!state.status(&[]).paused);
+    }
+
+    #[test]
+    fn test_timed_pause_expires() {
+        let mut state = PauseState::default();
+
+        let until = Utc::now() + chrono::Duration::minutes(5);
+        state.pause(Some(until));
+        let status = state.status(&[]);
+        assert!(status.paused);
+        assert_eq!(status.reason, Some(PauseReason::Timer));
+        assert_eq!(status.until, Some(until));
+
+        state.pause(Some(Utc::now() - chrono::Duration::seconds(1)));
+        assert!(!state.status(&[]).paused);
+    }
+
+    #[test]
+    fn test_resume_skips_current_quiet_hours() {
+        let every_day = [
+            Weekday::Mon,
+            Weekday::Tue,
+            Weekday::Wed,
+            Weekday::Thu,
+            Weekday::Fri,
+            Weekday::Sat,
+            Weekday::Sun,
+        ];
+        // Spans the whole day so the test doesn't depend on the clock
+        let schedule = [quiet_hours(&every_day, "00:00", "23:59")];
+        let mut state = PauseState::default();
+
+        let status = state.status(&schedule);
+        if !status.paused {
+            // Running in the one minute of the day outside the window
+            return;
+        }
+        assert_eq!(status.reason, Some(PauseReason::Schedule));
+
+        state.resume(&schedule);
+        assert!(!state.status(&schedule).paused);
+    }
+
+    #[test]
+    fn test_deferred_add_and_discard() {
+        let deferred = DeferredScreenshots::open(None);
+        let a = PathBuf::from("/Users/me/Desktop/Screenshot 1.png");
+        let b = PathBuf::from("/Users/me/Desktop/Screenshot 2.png");
+        assert!(deferred.add(a.clone()));
+        assert!(deferred.add(b.clone()));
+        // Seen again, e.g. a second event for the same file
+        assert!(!deferred.add(a.clone()));
+        assert_eq!(deferred.items().len(), 2);
+
+        deferred.discard(Some(&[a]));
+        let paths: Vec<PathBuf> = deferred.items().into_iter().map(|d| d.path).collect();
+        assert_eq!(paths, vec![b]);
+
+        deferred.discard(None);
+        assert!(deferred.is_empty());
+    }
+
+    #[test]
+    fn test_deferred_survives_reopen() {
+        let file = PathBuf::from("tests/assets/deferred_reopen.json");
+        std::fs::create_dir_all("tests/assets").unwrap();
+        let _ = std::fs::remove_file(&file);
+
+        let deferred = DeferredScreenshots::open(Some(file.clone()));
+        deferred.add(PathBuf::from("/Users/me/Desktop/Screenshot 1.png"));
+        deferred.add(PathBuf::from("/Users/me/Desktop/Screenshot 2.png"));
+        let items = deferred.items();
+        drop(deferred);
+
+        let reopened = DeferredScreenshots::open(Some(file.clone()));
+        assert_eq!(reopened.items(), items);
+
+        // Discarding is saved too
+        reopened.discard(Some(&[items[0].path.clone()]));
+        let reopened = DeferredScreenshots::open(Some(file.clone()));
+        assert_eq!(reopened.items(), vec![items[1].clone()]);
+
+        std::fs::remove_file(&file).unwrap();
+    }
+
+    #[test]
+    fn test_flush_on_resume() {
+        let dir = PathBuf::from("tests/assets/pause_flush");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("deferred.json");
+        let kept = dir.join("Screenshot 1.png");
+        let deleted = dir.join("Screenshot 2.png");
+        std::fs::write(&kept, b"capture").unwrap();
+
+        let mut state = PauseState::default();
+        state.pause(None);
+        assert!(state.status(&[]).paused);
+
+        let deferred = DeferredScreenshots::open(Some(file.clone()));
+        deferred.add(kept.clone());
+        deferred.add(deleted);
+
+        // A queue without workers, so the jobs stay queued
+        let queue = JobQueue::new(None, |_, _| {});
+        state.resume(&[]);
+        assert!(!state.status(&[]).paused);
+        assert_eq!(deferred.flush(&queue), 1);
+
+        // Only the file that still exists is named
+        let queued: Vec<PathBuf> = queue.jobs().into_iter().map(|job| job.path).collect();
+        assert_eq!(queued, vec![kept]);
+        assert!(deferred.is_empty());
+        assert!(DeferredScreenshots::open(Some(file)).is_empty());
+        assert_eq!(deferred.flush(&queue), 0);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,544 @@
+use chrono::Local;
+use serde::Serialize;
+use std::{
+    fs,
+    panic::{self, AssertUnwindSafe},
+    path::{Path, PathBuf},
+    time::Instant,
+};
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_notification::NotificationExt;
+
+use crate::{
+    folders::{folder_mode, FolderMode},
+    generate_name::generate_screenshot_name,
+    ledger::{hash_file, ProcessedLedger},
+    naming::{fallback_name, NamingError},
+    offline_queue::emit_offline,
+    queue::{CancellationToken, Job, JobQueue, JobState},
+    rename::{target_path, ConflictStrategy},
+    rules::{apply_tags, evaluate_rules, RuleInput},
+    sanitize::{detect_filesystem, sanitize_file_name},
+    settings::Settings,
+    state::AppState,
+    template::{capture_time, render_template, uses_sequence, CaseStyle, TemplateContext},
+    user::UserDevice,
+    watch::{get_file_extension, move_file, rename_file},
+};
+
+/// Runs one queued screenshot through naming and renaming on a worker thread.
+pub fn process_job(
+    queue: &JobQueue,
+    job: Job,
+    user_device: &UserDevice,
+    ledger: &ProcessedLedger,
+    app_handle: &AppHandle,
+) {
+    // A panic must not take the worker down with it
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        process_screenshot(
+            queue,
+            job.id,
+            &job.path,
+            &job.cancel,
+            user_device,
+            ledger,
+            app_handle,
+        )
+    }))
+    .unwrap_or_else(|_| Err("Worker panicked while processing screenshot".to_string()));
+
+    let state = app_handle.state::<AppState>();
+
+    // Anything but another network failure means the offline queue is done
+    // with it
+    if !matches!(result, Ok(Processed::Offline(_))) && state.offline.remove(&job.path) {
+        emit_offline(app_handle, &state);
+    }
+
+    match result {
+        Ok(Processed::Offline(error)) => queue.hold_offline(job.id, error),
+        Ok(Processed::Renamed(name)) => queue.finish(job.id, Ok(name)),
+        Ok(Processed::Skipped) => queue.skip(job.id),
+        Ok(Processed::Pending(name)) => queue.hold_for_review(job.id, name),
+        Err(e) => {
+            eprintln!("❌ Failed to process {}: {}", job.path.display(), e);
+            queue.finish(job.id, Err(e));
+        }
+    }
+}
+
+/// How a screenshot left the pipeline, short of an error.
+enum Processed {
+    Renamed(String),
+    /// Left under its original name by a rule, or naming was cancelled
+    Skipped,
+    /// The folder is in review mode; the name is in the inbox
+    Pending(String),
+    /// Waiting in the offline queue, with the error that put it there
+    Offline(String),
+}
+
+/// Names and renames one screenshot.
+fn process_screenshot(
+    queue: &JobQueue,
+    job_id: u64,
+    path: &Path,
+    cancel: &CancellationToken,
+    user_device: &UserDevice,
+    ledger: &ProcessedLedger,
+    app_handle: &AppHandle,
+) -> Result<Processed, String> {
+    if !path.exists() {
+        return Err(format!("File no longer exists: {}", path.display()));
+    }
+    // Checked again here, as the watcher may have queued a file another job
+    // was still renaming
+    if ledger.is_processed(path) {
+        println!("⏭️ Already named, skipping: {}", path.display());
+        return Ok(Processed::Skipped);
+    }
+    // Only recorded in the ledger once renamed, so found again by catch-up
+    // while it waits for review
+    if app_handle.state::<AppState>().inbox.contains(path) {
+        println!("📥 Already waiting for review: {}", path.display());
+        return Ok(Processed::Skipped);
+    }
+
+    println!("🖼️ Processing new screenshot: {}", path.display());
+
+    queue.set_state(job_id, JobState::Uploading);
+    let naming_started = Instant::now();
+    let outcome = match generate_screenshot_name(path, user_device, app_handle, cancel) {
+        Ok(outcome) => outcome,
+        Err(e @ NamingError::Cancelled) => {
+            // Not recorded in the ledger, so a later catch-up can try again
+            println!("⏹️ Naming cancelled: {}", path.display());
+            emit_failed(app_handle, path, &e, false, None);
+            return Ok(Processed::Skipped);
+        }
+        Err(e) if e.is_transient() => {
+            let state = app_handle.state::<AppState>();
+            let item = state.offline.add(path, &e);
+            println!(
+                "📴 Naming failed {} time(s), waiting for the connection: {}",
+                item.attempts,
+                path.display()
+            );
+            emit_offline(app_handle, &state);
+            emit_failed(app_handle, path, &e, true, None);
+            return Ok(Processed::Offline(e.to_string()));
+        }
+        Err(e) => {
+            // Not recorded in the ledger, so a later catch-up can try again
+            emit_failed(app_handle, path, &e, false, None);
+            return Err(e.to_string());
+        }
+    };
+    if let Some(reason) = &outcome.fallback_reason {
+        emit_failed(app_handle, path, reason, false, Some(&outcome.name));
+    }
+    let naming_ms = (naming_started.elapsed().as_millis() as u64).saturating_sub(outcome.wait_ms);
+    let origin = NameOrigin {
+        generated_name: outcome.name.clone(),
+        backend: Some(outcome.backend.to_string()),
+        wait_ms: Some(outcome.wait_ms),
+        naming_ms: Some(naming_ms),
+        prepare_started: Instant::now(),
+    };
+    let suggested = outcome.name;
+    println!("📁 Suggested name: {} ({})", suggested, outcome.backend);
+
+    let state = app_handle.state::<AppState>();
+    let settings = state.settings.lock().unwrap().clone();
+
+    let rules = state.rules.lock().unwrap().clone();
+    let input = RuleInput {
+        path,
+        name: &suggested,
+        size: fs::metadata(path).map(|m| m.len()).unwrap_or(0),
+        dimensions: image::image_dimensions(path).ok(),
+        captured_at: capture_time(path),
+        tier: &user_device.tier,
+    };
+    let outcome = evaluate_rules(&rules, &input);
+    if !outcome.matched.is_empty() {
+        println!("📐 Matched rules: {}", outcome.matched.join(", "));
+    }
+
+    if outcome.skip {
+        println!("⏭️ Skipped by rule: {}", path.display());
+        // Left under its name on purpose, so don't send it again after a
+        // restart
+        match hash_file(path) {
+            Ok(hash) => ledger.record(hash, path, path, &suggested),
+            Err(e) => eprintln!("❌ Failed to hash {}: {}", path.display(), e),
+        }
+        return Ok(Processed::Skipped);
+    }
+
+    let template = outcome
+        .template
+        .clone()
+        .unwrap_or_else(|| settings.name_template.clone());
+    let context = template_context(&state, &settings, &template, path, &suggested);
+
+    let templated = match render_template(&template, settings.name_case, &context) {
+        Ok(name) => name,
+        Err(e) => {
+            eprintln!("❌ Failed to apply name template: {}", e);
+            suggested
+        }
+    };
+
+    let parent_dir = path
+        .parent()
+        .ok_or_else(|| format!("Can't determine parent directory of {}", path.display()))?;
+    // A rule's destination wins over the organize tree
+    let organize_dir = match outcome.move_to {
+        Some(dir) => Some(dir),
+        None if settings.organize.enabled => {
+            match settings.organize.destination_dir(path, &context) {
+                Ok(dir) => Some(dir),
+                Err(e) => {
+                    eprintln!(
+                        "❌ Failed to resolve organize folder, renaming in place: {}",
+                        e
+                    );
+                    None
+                }
+            }
+        }
+        None => None,
+    };
+    let target_dir = organize_dir.as_deref().unwrap_or(parent_dir);
+
+    let ext = get_file_extension(path);
+    let name = match sanitize_for(target_dir, ext, &templated) {
+        Ok(name) => name,
+        Err(e) => {
+            eprintln!("❌ Unusable suggested name: {}", e);
+            sanitize_for(target_dir, ext, &fallback_name(path))?
+        }
+    };
+
+    notify_rules(app_handle, &outcome.notifications, &context, &name);
+
+    let folders = state.watched_folders.lock().unwrap().clone();
+    if folder_mode(&folders, path) == FolderMode::Review {
+        let item = state
+            .inbox
+            .add(path.to_path_buf(), name.clone(), organize_dir, outcome.tags);
+        println!("📥 Waiting for review: {} → {}", path.display(), name);
+        if let Err(e) = app_handle.emit_to("main", "rename-pending", item) {
+            eprintln!("❌ Failed to emit event: {:?}", e);
+        }
+        return Ok(Processed::Pending(name));
+    }
+
+    queue.set_state(job_id, JobState::Renaming);
+    let renamed = apply_rename(
+        app_handle,
+        path,
+        &name,
+        organize_dir.as_deref(),
+        settings.conflict_strategy,
+        outcome.tags,
+        origin,
+    );
+    match renamed.status {
+        RenamedStatus::Renamed => Ok(Processed::Renamed(name)),
+        RenamedStatus::Kept => Ok(Processed::Skipped),
+        RenamedStatus::Failed => Err(renamed.error.unwrap_or_default()),
+    }
+}
+
+/// How a name was arrived at, carried through to the `screenshot-renamed`
+/// event. Stages that didn't run, as for names accepted from the inbox, are
+/// `None`.
+struct NameOrigin {
+    generated_name: String,
+    backend: Option<String>,
+    wait_ms: Option<u64>,
+    naming_ms: Option<u64>,
+    /// When rules, templates and sanitizing started
+    prepare_started: Instant,
+}
+
+/// Moves the screenshot to its new name, tags it and tells the UI.
+fn apply_rename(
+    app_handle: &AppHandle,
+    path: &Path,
+    name: &String,
+    target_dir: Option<&Path>,
+    strategy: ConflictStrategy,
+    tags: Vec<String>,
+    origin: NameOrigin,
+) -> RenamedScreenshot {
+    let prepare_ms = origin.prepare_started.elapsed().as_millis() as u64;
+    let size = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    // Renaming leaves the contents alone, so this matches the file at its new
+    // path
+    let hash = hash_file(path)
+        .inspect_err(|e| eprintln!("❌ Failed to hash {}: {}", path.display(), e))
+        .ok();
+
+    // Recorded under the name the file should get before it moves, so the
+    // watcher takes the new name as done rather than as another screenshot to
+    // name. A suffixed name is only known afterwards, which the worker's own
+    // ledger check covers.
+    let state = app_handle.state::<AppState>();
+    let expected = target_dir
+        .or(path.parent())
+        .map(|dir| target_path(dir, name, get_file_extension(path)));
+    let claimed = match (&hash, &expected) {
+        (Some(hash), Some(expected)) if !state.ledger.contains(hash, expected) => {
+            state.ledger.record(hash.clone(), path, expected, name);
+            true
+        }
+        _ => false,
+    };
+
+    let rename_started = Instant::now();
+    let result = match target_dir {
+        Some(dir) => move_file(path, dir, name, strategy),
+        None => rename_file(path, name, strategy),
+    };
+    let rename_ms = rename_started.elapsed().as_millis() as u64;
+
+    let (status, final_path, error) = match result {
+        Ok(Some(final_path)) => (RenamedStatus::Renamed, Some(final_path), None),
+        Ok(None) => (RenamedStatus::Kept, None, None),
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            (RenamedStatus::Failed, None, Some(e))
+        }
+    };
+
+    if final_path != expected {
+        // Renamed to a suffixed name, or not at all
+        if let (true, Some(hash), Some(expected)) = (claimed, &hash, &expected) {
+            state.ledger.forget(hash, expected);
+        }
+        if let (Some(hash), Some(final_path)) = (&hash, &final_path) {
+            state.ledger.record(hash.clone(), path, final_path, name);
+        }
+    }
+    if let Some(final_path) = &final_path {
+        if let Err(e) = apply_tags(final_path, &tags) {
+            eprintln!("❌ Failed to tag {}: {}", final_path.display(), e);
+        }
+        state.history.record(path, final_path, name, hash);
+    }
+
+    let renamed = RenamedScreenshot {
+        status,
+        name: name.clone(),
+        generated_name: origin.generated_name,
+        backend: origin.backend,
+        original_path: path.to_path_buf(),
+        path: final_path,
+        size,
+        durations: StageDurations {
+            wait_ms: origin.wait_ms,
+            naming_ms: origin.naming_ms,
+            prepare_ms,
+            rename_ms,
+        },
+        tags,
+        error,
+    };
+    if let Err(e) = app_handle.emit_to("main", "screenshot-renamed", &renamed) {
+        eprintln!("❌ Failed to emit event: {:?}", e);
+    }
+    renamed
+}
+
+/// Applies a suggestion from the review inbox, with `name` replacing the
+/// suggested one if given.
+pub fn accept_pending(
+    app_handle: &AppHandle,
+    id: u64,
+    name: Option<String>,
+) -> Result<RenamedScreenshot, String> {
+    let state = app_handle.state::<AppState>();
+    let item = state
+        .inbox
+        .get(id)
+        .ok_or_else(|| format!("No pending rename with id {}", id))?;
+
+    if !item.path.exists() {
+        state.inbox.take(id);
+        return Err(format!("File no longer exists: {}", item.path.display()));
+    }
+
+    let name = match name {
+        Some(name) => {
+            let dir = match &item.target_dir {
+                Some(dir) => dir.as_path(),
+                None => item
+                    .path
+                    .parent()
+                    .ok_or("Can't determine parent directory")?,
+            };
+            sanitize_for(dir, get_file_extension(&item.path), &name)?
+        }
+        None => item.suggested_name.clone(),
+    };
+
+    let strategy = state.settings.lock().unwrap().conflict_strategy;
+    let origin = NameOrigin {
+        generated_name: item.suggested_name.clone(),
+        backend: None,
+        wait_ms: None,
+        naming_ms: None,
+        prepare_started: Instant::now(),
+    };
+    let renamed = apply_rename(
+        app_handle,
+        &item.path,
+        &name,
+        item.target_dir.as_deref(),
+        strategy,
+        item.tags.clone(),
+        origin,
+    );
+    match renamed.status {
+        RenamedStatus::Renamed => {}
+        RenamedStatus::Kept => return Err(format!("'{}' is already taken", name)),
+        RenamedStatus::Failed => return Err(renamed.error.unwrap_or_default()),
+    }
+
+    state.inbox.take(id);
+    Ok(renamed)
+}
+
+/// Shows the notifications asked for by matching rules.
+fn notify_rules(
+    app_handle: &AppHandle,
+    messages: &[String],
+    context: &TemplateContext,
+    name: &str,
+) {
+    // Messages refer to the final name, not the raw suggestion
+    let context = TemplateContext {
+        name: name.to_string(),
+        ..context.clone()
+    };
+    for message in messages {
+        let body = render_template(message, CaseStyle::Original, &context)
+            .unwrap_or_else(|_| message.clone());
+        if let Err(e) = app_handle
+            .notification()
+            .builder()
+            .title("SnapName")
+            .body(body)
+            .show()
+        {
+            eprintln!("❌ Failed to show notification: {:?}", e);
+        }
+    }
+}
+
+/// Payload of the `screenshot-failed` event.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScreenshotFailed<'a> {
+    pub path: &'a Path,
+    pub error: &'a NamingError,
+    /// Readable form of `error`
+    pub message: String,
+    /// Whether it waits in the offline queue to be named again
+    pub will_retry: bool,
+    /// Name used instead, if the fallback setting allowed one
+    pub fallback_name: Option<&'a str>,
+}
+
+fn emit_failed(
+    app_handle: &AppHandle,
+    path: &Path,
+    error: &NamingError,
+    will_retry: bool,
+    fallback_name: Option<&str>,
+) {
+    let failed = ScreenshotFailed {
+        path,
+        error,
+        message: error.to_string(),
+        will_retry,
+        fallback_name,
+    };
+    if let Err(e) = app_handle.emit_to("main", "screenshot-failed", failed) {
+        eprintln!("❌ Failed to emit event: {:?}", e);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RenamedStatus {
+    Renamed,
+    /// The name was taken and the conflict strategy is `Skip`
+    Kept,
+    Failed,
+}
+
+/// Milliseconds spent in each stage; `None` for stages that didn't run.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StageDurations {
+    /// Waiting for the file to finish being written
+    pub wait_ms: Option<u64>,
+    pub naming_ms: Option<u64>,
+    /// Rules, templates and sanitizing
+    pub prepare_ms: u64,
+    pub rename_ms: u64,
+}
+
+/// Payload of the `screenshot-renamed` event, sent whether or not the rename
+/// went through.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RenamedScreenshot {
+    pub status: RenamedStatus,
+    /// The name given to the file, without extension
+    pub name: String,
+    /// The name as the backend suggested it, before templates and sanitizing
+    pub generated_name: String,
+    /// `None` for names accepted from the review inbox
+    pub backend: Option<String>,
+    pub original_path: PathBuf,
+    /// Where the file ended up; `None` unless renamed
+    pub path: Option<PathBuf>,
+    /// Size in bytes
+    pub size: u64,
+    pub durations: StageDurations,
+    /// Added by rules
+    pub tags: Vec<String>,
+    pub error: Option<String>,
+}
+
+/// Token values for the name template and organize folders.
+fn template_context(
+    state: &AppState,
+    settings: &Settings,
+    template: &str,
+    path: &Path,
+    suggested: &str,
+) -> TemplateContext {
+    let needs_seq = uses_sequence(template)
+        || (settings.organize.enabled && uses_sequence(&settings.organize.subfolders));
+    let seq = if needs_seq {
+        state.sequence.next(Local::now().date_naive())
+    } else {
+        0
+    };
+    let folders = state.watched_folders.lock().unwrap().clone();
+    TemplateContext::for_screenshot(path, suggested, &folders, seq)
+}
+
+/// Sanitizes `name` for the filesystem of `dir`.
+fn sanitize_for(dir: &Path, ext: &str, name: &str) -> Result<String, String> {
+    // The folder may not exist yet when organizing into a new month
+    let existing = dir.ancestors().find(|d| d.exists()).unwrap_or(dir);
+    sanitize_file_name(name, ext, detect_filesystem(existing))
+}
@@ -0,0 +1,517 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::{
+    collections::VecDeque,
+    path::PathBuf,
+    sync::{Arc, Condvar, Mutex},
+    time::Duration,
+};
+use tauri::{AppHandle, Emitter};
+
+/// Finished jobs kept around so the UI can show what just happened.
+const FINISHED_JOBS_KEPT: usize = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobState {
+    Queued,
+    Uploading,
+    Renaming,
+    Done,
+    Failed,
+    /// Left alone on purpose, e.g. by a rule
+    Skipped,
+    /// Named, but waiting in the review inbox
+    Review,
+    /// Naming failed on the network; retried once back online
+    Offline,
+}
+
+impl JobState {
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            JobState::Done
+                | JobState::Failed
+                | JobState::Skipped
+                | JobState::Review
+                | JobState::Offline
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Job {
+    pub id: u64,
+    pub path: PathBuf,
+    pub state: JobState,
+    pub name: Option<String>,
+    pub error: Option<String>,
+    pub queued_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    /// Fired by `JobQueue::cancel`, from the moment the job is queued
+    #[serde(skip)]
+    pub cancel: CancellationToken,
+}
+
+/// Tells a worker to stop what it's doing for a job. Naming checks it while
+/// waiting for the file, between attempts and after each request; a request
+/// already on the wire runs to its timeout, and its answer is dropped.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    inner: Arc<(Mutex<bool>, Condvar)>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        let (lock, cvar) = &*self.inner;
+        *lock.lock().unwrap() = true;
+        cvar.notify_all();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        *self.inner.0.lock().unwrap()
+    }
+
+    /// Waits for `duration`, or less if the token is cancelled first. Returns
+    /// whether it was.
+    pub fn sleep(&self, duration: Duration) -> bool {
+        let (lock, cvar) = &*self.inner;
+        let cancelled = lock.lock().unwrap();
+        let (cancelled, _) = cvar
+            .wait_timeout_while(cancelled, duration, |cancelled| !*cancelled)
+            .unwrap();
+        *cancelled
+    }
+}
+
+#[derive(Default)]
+struct QueueInner {
+    next_id: u64,
+    pending: VecDeque<u64>,
+    jobs: Vec<Job>,
+    worker_target: usize,
+    workers_running: usize,
+}
+
+type JobHandler = dyn Fn(&JobQueue, Job) + Send + Sync;
+
+/// Jobs waiting between the watcher and the naming workers.
+#[derive(Clone)]
+pub struct JobQueue {
+    inner: Arc<(Mutex<QueueInner>, Condvar)>,
+    handler: Arc<JobHandler>,
+    app_handle: Option<AppHandle>,
+}
+
+impl JobQueue {
+    /// `handler` runs on a worker thread for every job and must finish it.
+    /// Changes are sent to the UI through `app_handle`; `None` keeps them
+    /// quiet.
+    pub fn new<F>(app_handle: Option<AppHandle>, handler: F) -> Self
+    where
+        F: Fn(&JobQueue, Job) + Send + Sync + 'static,
+    {
+        JobQueue {
+            inner: Arc::new((Mutex::new(QueueInner::default()), Condvar::new())),
+            handler: Arc::new(handler),
+            app_handle,
+        }
+    }
+
+    /// Queues a screenshot unless it is already waiting or being processed.
+    pub fn enqueue(&self, path: PathBuf) -> Option<u64> {
+        let (lock, cvar) = &*self.inner;
+        let mut inner = lock.lock().unwrap();
+
+        if inner
+            .jobs
+            .iter()
+            .any(|job| job.path == path && !job.state.is_finished())
+        {
+            return None;
+        }
+
+        inner.next_id += 1;
+        let now = Utc::now();
+        let job = Job {
+            id: inner.next_id,
+            path,
+            state: JobState::Queued,
+            name: None,
+            error: None,
+            queued_at: now,
+            updated_at: now,
+            cancel: CancellationToken::new(),
+        };
+        inner.pending.push_back(job.id);
+        inner.jobs.push(job.clone());
+        drop(inner);
+
+        cvar.notify_one();
+        self.emit(&job);
+        Some(job.id)
+    }
+
+    pub fn jobs(&self) -> Vec<Job> {
+        self.inner.0.lock().unwrap().jobs.clone()
+    }
+
+    pub fn set_state(&self, id: u64, state: JobState) {
+        self.update(id, |job| job.state = state);
+    }
+
+    pub fn finish(&self, id: u64, result: Result<String, String>) {
+        self.update(id, |job| match result {
+            Ok(name) => {
+                job.state = JobState::Done;
+                job.name = Some(name);
+            }
+            Err(e) => {
+                job.state = JobState::Failed;
+                job.error = Some(e);
+            }
+        });
+        self.prune_finished();
+    }
+
+    pub fn skip(&self, id: u64) {
+        self.update(id, |job| job.state = JobState::Skipped);
+        self.prune_finished();
+    }
+
+    /// Stops a job: one still waiting is taken off the queue, one a worker
+    /// has is told to stop. Returns `false` if it has already finished.
+    pub fn cancel(&self, id: u64) -> bool {
+        {
+            let mut inner = self.inner.0.lock().unwrap();
+            if let Some(index) = inner.pending.iter().position(|&pending| pending == id) {
+                inner.pending.remove(index);
+            } else {
+                return match inner.jobs.iter().find(|job| job.id == id) {
+                    Some(job) if !job.state.is_finished() => {
+                        job.cancel.cancel();
+                        true
+                    }
+                    _ => false,
+                };
+            }
+        }
+        self.skip(id);
+        true
+    }
+
+    pub fn hold_for_review(&self, id: u64, name: String) {
+        self.update(id, |job| {
+            job.state = JobState::Review;
+            job.name = Some(name);
+        });
+        self.prune_finished();
+    }
+
+    pub fn hold_offline(&self, id: u64, error: String) {
+        self.update(id, |job| {
+            job.state = JobState::Offline;
+            job.error = Some(error);
+        });
+        self.prune_finished();
+    }
+
+    fn update(&self, id: u64, change: impl FnOnce(&mut Job)) {
+        let job = {
+            let mut inner = self.inner.0.lock().unwrap();
+            let Some(job) = inner.jobs.iter_mut().find(|job| job.id == id) else {
+                return;
+            };
+            change(job);
+            job.updated_at = Utc::now();
+            job.clone()
+        };
+        self.emit(&job);
+    }
+
+    fn prune_finished(&self) {
+        let mut inner = self.inner.0.lock().unwrap();
+        let finished = inner
+            .jobs
+            .iter()
+            .filter(|job| job.state.is_finished())
+            .count();
+        let mut to_drop = finished.saturating_sub(FINISHED_JOBS_KEPT);
+        inner.jobs.retain(|job| {
+            if to_drop > 0 && job.state.is_finished() {
+                to_drop -= 1;
+                return false;
+            }
+            true
+        });
+    }
+
+    fn emit(&self, job: &Job) {
+        let Some(app_handle) = &self.app_handle else {
+            return;
+        };
+        if let Err(e) = app_handle.emit_to("main", "queue-updated", job) {
+            eprintln!("❌ Failed to emit event: {:?}", e);
+        }
+    }
+
+    /// Blocks until a job is available. Returns `None` when this worker should
+    /// exit because the pool was shrunk.
+    fn next_job(&self) -> Option<Job> {
+        let (lock, cvar) = &*self.inner;
+        let mut inner = lock.lock().unwrap();
+        loop {
+            if inner.workers_running > inner.worker_target {
+                inner.workers_running -= 1;
+                return None;
+            }
+            if let Some(id) = inner.pending.pop_front() {
+                if let Some(job) = inner.jobs.iter().find(|job| job.id == id) {
+                    return Some(job.clone());
+                }
+                continue;
+            }
+            inner = cvar.wait(inner).unwrap();
+        }
+    }
+
+    /// Grows or shrinks the worker pool. Extra workers exit once they finish
+    /// their current job.
+    pub fn set_worker_count(&self, count: usize) {
+        let (lock, cvar) = &*self.inner;
+        let mut inner = lock.lock().unwrap();
+        inner.worker_target = count;
+
+        while inner.workers_running < count {
+            inner.workers_running += 1;
+            let queue = self.clone();
+            std::thread::spawn(move || {
+                while let Some(job) = queue.next_job() {
+                    (queue.handler)(&queue, job);
+                }
+            });
+        }
+        drop(inner);
+
+        // Wake idle workers so surplus ones notice they should exit
+        cvar.notify_all();
+        println!("👷 Naming workers: {}", count);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    /// A queue without workers, so jobs stay where the test puts them.
+    fn idle_queue() -> JobQueue {
+        JobQueue::new(None, |_, _| {})
+    }
+
+    fn state_of(queue: &JobQueue, id: u64) -> JobState {
+        queue.jobs().iter().find(|job| job.id == id).unwrap().state
+    }
+
+    fn wait_for(condition: impl Fn() -> bool) {
+        let started = Instant::now();
+        while !condition() {
+            assert!(
+                started.elapsed() < Duration::from_secs(5),
+                "Timed out waiting for the workers"
+            );
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn test_enqueue_skips_files_already_queued() {
+        let queue = idle_queue();
+        let first = queue
+            .enqueue(PathBuf::from("/tmp/Screenshot 1.png"))
+            .unwrap();
+        assert_eq!(queue.enqueue(PathBuf::from("/tmp/Screenshot 1.png")), None);
+        assert!(queue
+            .enqueue(PathBuf::from("/tmp/Screenshot 2.png"))
+            .is_some());
+        assert_eq!(queue.jobs().len(), 2);
+
+        // Once finished, the same file can be queued again, e.g. when modified
+        queue.finish(first, Ok("login page".to_string()));
+        let again = queue
+            .enqueue(PathBuf::from("/tmp/Screenshot 1.png"))
+            .unwrap();
+        assert_ne!(again, first);
+    }
+
+    #[test]
+    fn test_job_state_transitions() {
+        let queue = idle_queue();
+        let ids: Vec<u64> = (1..=5)
+            .map(|i| {
+                queue
+                    .enqueue(PathBuf::from(format!("/tmp/Screenshot {}.png", i)))
+                    .unwrap()
+            })
+            .collect();
+        assert!(ids
+            .iter()
+            .all(|&id| state_of(&queue, id) == JobState::Queued));
+
+        queue.set_state(ids[0], JobState::Uploading);
+        assert_eq!(state_of(&queue, ids[0]), JobState::Uploading);
+        queue.set_state(ids[0], JobState::Renaming);
+        queue.finish(ids[0], Ok("login page".to_string()));
+        queue.finish(ids[1], Err("File no longer exists".to_string()));
+        queue.skip(ids[2]);
+        queue.hold_for_review(ids[3], "invoice".to_string());
+        queue.hold_offline(ids[4], "Network error".to_string());
+
+        let jobs = queue.jobs();
+        let states: Vec<JobState> = jobs.iter().map(|job| job.state).collect();
+        assert_eq!(
+            states,
+            vec![
+                JobState::Done,
+                JobState::Failed,
+                JobState::Skipped,
+                JobState::Review,
+                JobState::Offline
+            ]
+        );
+        assert!(states.iter().all(JobState::is_finished));
+        assert_eq!(jobs[0].name.as_deref(), Some("login page"));
+        assert_eq!(jobs[1].error.as_deref(), Some("File no longer exists"));
+        assert_eq!(jobs[3].name.as_deref(), Some("invoice"));
+        assert_eq!(jobs[4].error.as_deref(), Some("Network error"));
+
+        // Unknown ids are ignored
+        queue.finish(999, Ok("nothing".to_string()));
+        assert_eq!(queue.jobs().len(), 5);
+    }
+
+    #[test]
+    fn test_only_recent_finished_jobs_are_kept() {
+        let queue = idle_queue();
+        let waiting = queue.enqueue(PathBuf::from("/tmp/waiting.png")).unwrap();
+        for i in 0..FINISHED_JOBS_KEPT + 10 {
+            let id = queue
+                .enqueue(PathBuf::from(format!("/tmp/{}.png", i)))
+                .unwrap();
+            queue.finish(id, Ok(i.to_string()));
+        }
+
+        let jobs = queue.jobs();
+        assert_eq!(jobs.len(), FINISHED_JOBS_KEPT + 1);
+        assert_eq!(state_of(&queue, waiting), JobState::Queued);
+        // Oldest ones go first
+        assert_eq!(jobs[1].name.as_deref(), Some("10"));
+    }
+
+    #[test]
+    fn test_worker_pool_resizing() {
+        // Each job reports that it started, then holds its worker until the
+        // test lets one job go
+        let (started_tx, started) = mpsc::channel();
+        let (release, release_rx) = mpsc::channel::<()>();
+        let started_tx = Mutex::new(started_tx);
+        let release_rx = Mutex::new(release_rx);
+        let running = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let (worker_running, worker_peak) = (running.clone(), peak.clone());
+        let queue = JobQueue::new(None, move |queue, job| {
+            let now = worker_running.fetch_add(1, Ordering::SeqCst) + 1;
+            worker_peak.fetch_max(now, Ordering::SeqCst);
+            started_tx.lock().unwrap().send(job.id).unwrap();
+            release_rx.lock().unwrap().recv().unwrap();
+            worker_running.fetch_sub(1, Ordering::SeqCst);
+            queue.finish(job.id, Ok(job.path.display().to_string()));
+        });
+        let all_done = || queue.jobs().iter().all(|job| job.state == JobState::Done);
+        let wait_started = || started.recv_timeout(Duration::from_secs(5)).unwrap();
+        let none_started = || started.recv_timeout(Duration::from_millis(50)).is_err();
+
+        queue.set_worker_count(3);
+        for i in 0..9 {
+            queue.enqueue(PathBuf::from(format!("/tmp/burst {}.png", i)));
+        }
+        // Three jobs run at once, and no fourth until one is released
+        for _ in 0..3 {
+            wait_started();
+        }
+        assert!(none_started());
+        for _ in 0..9 {
+            release.send(()).unwrap();
+        }
+        wait_for(all_done);
+        while started.try_recv().is_ok() {}
+        assert!(peak.load(Ordering::SeqCst) <= 3);
+
+        // Surplus workers exit, leaving one job at a time
+        queue.set_worker_count(1);
+        peak.store(0, Ordering::SeqCst);
+        for i in 0..4 {
+            queue.enqueue(PathBuf::from(format!("/tmp/single {}.png", i)));
+        }
+        for _ in 0..4 {
+            wait_started();
+            assert!(none_started());
+            release.send(()).unwrap();
+        }
+        wait_for(all_done);
+        assert_eq!(peak.load(Ordering::SeqCst), 1);
+        assert_eq!(queue.jobs().len(), 13);
+    }
+
+    #[test]
+    fn test_cancel_waiting_and_taken_jobs() {
+        let queue = idle_queue();
+        let first = queue.enqueue(PathBuf::from("/tmp/first.png")).unwrap();
+        let second = queue.enqueue(PathBuf::from("/tmp/second.png")).unwrap();
+
+        assert!(queue.cancel(first));
+        assert_eq!(state_of(&queue, first), JobState::Skipped);
+        assert!(!queue.cancel(first));
+
+        // A worker only picks up what's still waiting. Once it has the job,
+        // even before it starts naming, cancelling reaches it through the
+        // token
+        let taken = queue.next_job().unwrap();
+        assert_eq!(taken.id, second);
+        assert!(!taken.cancel.is_cancelled());
+        assert!(queue.cancel(second));
+        assert!(taken.cancel.is_cancelled());
+
+        queue.skip(second);
+        assert!(!queue.cancel(second));
+        assert!(!queue.cancel(42));
+    }
+
+    #[test]
+    fn test_cancellation_token_sleep() {
+        let token = CancellationToken::new();
+        let started = Instant::now();
+        assert!(!token.sleep(Duration::from_millis(20)));
+        assert!(started.elapsed() >= Duration::from_millis(20));
+
+        let canceller = token.clone();
+        let handle = thread::spawn(move || canceller.cancel());
+        // Wakes as soon as the token fires, well before the full wait
+        let started = Instant::now();
+        assert!(token.sleep(Duration::from_secs(30)));
+        assert!(started.elapsed() < Duration::from_secs(5));
+        handle.join().unwrap();
+
+        assert!(token.is_cancelled());
+        assert!(token.sleep(Duration::from_secs(30)));
+    }
+}
@@ -0,0 +1,417 @@
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// What to do when a file with the suggested name already exists.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictStrategy {
+    /// `login-page-2.png`, `login-page-3.png`, ...
+    #[default]
+    Numeric,
+    /// `login-page-20250612-102233.png`
+    Timestamp,
+    /// Leave the screenshot under its original name
+    Skip,
+}
+
+/// Highest suffix tried before giving up on finding a free name.
+const MAX_SUFFIX: u32 = 999;
+
+/// Longest suffix a conflict can add, with a timestamp and a number. Names
+/// leave room for it so the suffixed name stays within filesystem limits.
+pub const MAX_SUFFIX_LEN: usize = "-20250612-102233-999".len();
+
+/// Moves `from` to `stem.ext` inside `dir`, possibly on another volume,
+/// without ever replacing an existing file. Returns where the file ended up,
+/// or `None` when `strategy` is `Skip` and the name was taken.
+pub fn rename_no_clobber(
+    from: &Path,
+    dir: &Path,
+    stem: &str,
+    ext: &str,
+    strategy: ConflictStrategy,
+) -> io::Result<Option<PathBuf>> {
+    let target = target_path(dir, stem, ext);
+
+    // On a case-insensitive filesystem "Login.png" -> "login.png" finds the
+    // target taken by the very file being renamed
+    if is_same_file(from, &target) {
+        fs::rename(from, &target)?;
+        return Ok(Some(target));
+    }
+
+    let suffixes: Vec<String> = match strategy {
+        ConflictStrategy::Skip => Vec::new(),
+        ConflictStrategy::Numeric => (2..=MAX_SUFFIX).map(|n| n.to_string()).collect(),
+        ConflictStrategy::Timestamp => {
+            let timestamp = Local::now().format("%Y%m%d-%H%M%S").to_string();
+            std::iter::once(timestamp.clone())
+                .chain((2..=MAX_SUFFIX).map(|n| format!("{}-{}", timestamp, n)))
+                .collect()
+        }
+    };
+
+    let candidates = std::iter::once(target).chain(
+        suffixes
+            .iter()
+            .map(|s| dir.join(file_name(stem, Some(s), ext))),
+    );
+    for candidate in candidates {
+        let res = match rename_noreplace(from, &candidate) {
+            // Moving to another volume, e.g. an external drive
+            Err(e) if is_cross_device(&e) => copy_then_delete(from, &candidate),
+            res => res,
+        };
+        match res {
+            Ok(()) => return Ok(Some(candidate)),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+
+    match strategy {
+        ConflictStrategy::Skip => Ok(None),
+        _ => Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("No free file name left for '{}'", stem),
+        )),
+    }
+}
+
+/// Where `rename_no_clobber` puts the file when the name isn't taken.
+pub fn target_path(dir: &Path, stem: &str, ext: &str) -> PathBuf {
+    dir.join(file_name(stem, None, ext))
+}
+
+fn file_name(stem: &str, suffix: Option<&str>, ext: &str) -> String {
+    let mut name = match suffix {
+        Some(suffix) => format!("{}-{}", stem, suffix),
+        None => stem.to_string(),
+    };
+    if !ext.is_empty() {
+        name.push('.');
+        name.push_str(ext);
+    }
+    name
+}
+
+#[cfg(unix)]
+fn is_same_file(a: &Path, b: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    match (fs::metadata(a), fs::metadata(b)) {
+        (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+        _ => false,
+    }
+}
+
+#[cfg(not(unix))]
+fn is_same_file(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// Renames atomically, failing with `AlreadyExists` if `to` is taken.
+#[cfg(target_os = "linux")]
+fn rename_noreplace(from: &Path, to: &Path) -> io::Result<()> {
+    use std::{ffi::CString, os::unix::ffi::OsStrExt};
+
+    let from_c = CString::new(from.as_os_str().as_bytes())?;
+    let to_c = CString::new(to.as_os_str().as_bytes())?;
+    // Called through syscall() since older glibc and musl lack the wrapper
+    let res = unsafe {
+        libc::syscall(
+            libc::SYS_renameat2,
+            libc::AT_FDCWD,
+            from_c.as_ptr(),
+            libc::AT_FDCWD,
+            to_c.as_ptr(),
+            libc::RENAME_NOREPLACE,
+        )
+    };
+    if res == 0 {
+        return Ok(());
+    }
+
+    let err = io::Error::last_os_error();
+    match err.raw_os_error() {
+        // Kernel or filesystem without RENAME_NOREPLACE support
+        Some(libc::EINVAL) | Some(libc::ENOSYS) | Some(libc::EOPNOTSUPP) => link_rename(from, to),
+        _ => Err(err),
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn rename_noreplace(from: &Path, to: &Path) -> io::Result<()> {
+    use std::{ffi::CString, os::unix::ffi::OsStrExt};
+
+    let from_c = CString::new(from.as_os_str().as_bytes())?;
+    let to_c = CString::new(to.as_os_str().as_bytes())?;
+    let res = unsafe { libc::renamex_np(from_c.as_ptr(), to_c.as_ptr(), libc::RENAME_EXCL) };
+    if res == 0 {
+        return Ok(());
+    }
+
+    let err = io::Error::last_os_error();
+    match err.raw_os_error() {
+        // Filesystems like FAT that don't support RENAME_EXCL
+        Some(libc::EINVAL) | Some(libc::ENOTSUP) => link_rename(from, to),
+        _ => Err(err),
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn rename_noreplace(from: &Path, to: &Path) -> io::Result<()> {
+    link_rename(from, to)
+}
+
+#[cfg(unix)]
+fn is_cross_device(e: &io::Error) -> bool {
+    e.raw_os_error() == Some(libc::EXDEV)
+}
+
+#[cfg(windows)]
+fn is_cross_device(e: &io::Error) -> bool {
+    // ERROR_NOT_SAME_DEVICE
+    e.raw_os_error() == Some(17)
+}
+
+/// Fallback for moves across filesystems. `create_new` keeps it no-clobber,
+/// and the source is only removed once the copy is safely on disk.
+fn copy_then_delete(from: &Path, to: &Path) -> io::Result<()> {
+    let mut source = fs::File::open(from)?;
+    let metadata = source.metadata()?;
+    let mut target = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(to)?;
+
+    let copied = io::copy(&mut source, &mut target)
+        .and_then(|_| target.set_permissions(metadata.permissions()))
+        .and_then(|_| target.sync_all());
+    if let Err(e) = copied {
+        let _ = fs::remove_file(to);
+        return Err(e);
+    }
+
+    // Keep the capture time, which dated folders and templates rely on
+    if let Ok(modified) = metadata.modified() {
+        let _ = target.set_modified(modified);
+    }
+    fs::remove_file(from)
+}
+
+/// Hard-linking fails atomically when `to` exists, so link then unlink is
+/// still no-clobber. Filesystems without hard links get a check-then-rename,
+/// which is only safe against other renames done by this app.
+fn link_rename(from: &Path, to: &Path) -> io::Result<()> {
+    match fs::hard_link(from, to) {
+        Ok(()) => fs::remove_file(from),
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => Err(e),
+        Err(_) => {
+            if to.exists() {
+                return Err(io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    format!("{} already exists", to.display()),
+                ));
+            }
+            fs::rename(from, to)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sanitize::{sanitize_file_name, FilesystemKind, MAX_NAME_CHARS};
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    /// Fresh directory per test so parallel tests don't see each other's files.
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = PathBuf::from("tests/assets").join(name);
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write(path: &Path, contents: &str) {
+        fs::write(path, contents).unwrap();
+    }
+
+    #[test]
+    fn test_renames_when_name_is_free() {
+        let dir = test_dir("rename_free");
+        let original = dir.join("Screenshot 1.png");
+        write(&original, "new");
+
+        let renamed = rename_no_clobber(
+            &original,
+            &dir,
+            "login-page",
+            "png",
+            ConflictStrategy::Numeric,
+        )
+        .unwrap();
+
+        assert_eq!(renamed, Some(dir.join("login-page.png")));
+        assert!(!original.exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_numeric_suffix_never_overwrites() {
+        let dir = test_dir("rename_numeric");
+        write(&dir.join("login-page.png"), "first");
+        write(&dir.join("login-page-2.png"), "second");
+        let original = dir.join("Screenshot 3.png");
+        write(&original, "third");
+
+        let renamed = rename_no_clobber(
+            &original,
+            &dir,
+            "login-page",
+            "png",
+            ConflictStrategy::Numeric,
+        )
+        .unwrap();
+
+        assert_eq!(renamed, Some(dir.join("login-page-3.png")));
+        assert_eq!(
+            fs::read_to_string(dir.join("login-page.png")).unwrap(),
+            "first"
+        );
+        assert_eq!(
+            fs::read_to_string(dir.join("login-page-2.png")).unwrap(),
+            "second"
+        );
+        assert_eq!(
+            fs::read_to_string(dir.join("login-page-3.png")).unwrap(),
+            "third"
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_timestamp_suffix() {
+        let dir = test_dir("rename_timestamp");
+        write(&dir.join("login-page.png"), "first");
+        let original = dir.join("Screenshot 2.png");
+        write(&original, "second");
+
+        let renamed = rename_no_clobber(
+            &original,
+            &dir,
+            "login-page",
+            "png",
+            ConflictStrategy::Timestamp,
+        )
+        .unwrap()
+        .unwrap();
+
+        let name = renamed.file_name().unwrap().to_str().unwrap();
+        // login-page-YYYYMMDD-HHMMSS.png
+        assert!(name.starts_with("login-page-"), "Got {}", name);
+        assert_eq!(name.len(), "login-page-20250612-102233.png".len());
+        assert_eq!(
+            fs::read_to_string(dir.join("login-page.png")).unwrap(),
+            "first"
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_skip_leaves_both_files() {
+        let dir = test_dir("rename_skip");
+        write(&dir.join("login-page.png"), "first");
+        let original = dir.join("Screenshot 2.png");
+        write(&original, "second");
+
+        let renamed =
+            rename_no_clobber(&original, &dir, "login-page", "png", ConflictStrategy::Skip)
+                .unwrap();
+
+        assert_eq!(renamed, None);
+        assert_eq!(fs::read_to_string(&original).unwrap(), "second");
+        assert_eq!(
+            fs::read_to_string(dir.join("login-page.png")).unwrap(),
+            "first"
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_case_only_rename() {
+        let dir = test_dir("rename_case");
+        let original = dir.join("Login-Page.png");
+        write(&original, "capture");
+
+        let renamed = rename_no_clobber(
+            &original,
+            &dir,
+            "login-page",
+            "png",
+            ConflictStrategy::Numeric,
+        )
+        .unwrap();
+
+        // Must not be treated as a conflict with itself on case-insensitive
+        // filesystems, and must not leave a "-2" copy behind
+        assert_eq!(renamed, Some(dir.join("login-page.png")));
+        let names: Vec<String> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(names, vec!["login-page.png".to_string()]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_suffix_fits_longest_name() {
+        let dir = test_dir("rename_longest");
+        let long = "😀".repeat(MAX_NAME_CHARS);
+        let stem = sanitize_file_name(&long, "png", FilesystemKind::Ext4).unwrap();
+        write(&dir.join(format!("{}.png", stem)), "taken");
+
+        for strategy in [ConflictStrategy::Numeric, ConflictStrategy::Timestamp] {
+            let from = dir.join("Screenshot.png");
+            write(&from, "new");
+            let renamed = rename_no_clobber(&from, &dir, &stem, "png", strategy)
+                .unwrap()
+                .unwrap();
+            assert!(renamed.file_name().unwrap().len() <= 255);
+            assert_eq!(fs::read_to_string(&renamed).unwrap(), "new");
+        }
+
+        // The longest suffix still fits
+        let longest = file_name(&stem, Some("20250612-102233-999"), "png");
+        assert!(longest.len() <= 255, "{} bytes", longest.len());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_renaming_to_own_name() {
+        let dir = test_dir("rename_same");
+        let original = dir.join("login-page.png");
+        write(&original, "capture");
+
+        let renamed = rename_no_clobber(
+            &original,
+            &dir,
+            "login-page",
+            "png",
+            ConflictStrategy::Numeric,
+        )
+        .unwrap();
+
+        assert_eq!(renamed, Some(original.clone()));
+        assert_eq!(fs::read_to_string(&original).unwrap(), "capture");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
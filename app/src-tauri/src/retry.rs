@@ -0,0 +1,224 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    time::Duration,
+};
+
+use crate::naming::NamingError;
+
+/// How often and how patiently to retry a naming request that failed for a
+/// reason that may go away, like a dropped connection or a 503.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Including the first try
+    pub max_attempts: u32,
+    /// Wait before the first retry, doubled for each one after
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 4,
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Wait before retry number `retry` (from 0). `jitter` in `0.0..=1.0`
+    /// spreads the wait over the upper half of the backoff so clients that
+    /// failed together don't all come back at once.
+    pub fn backoff(&self, retry: u32, jitter: f64) -> Duration {
+        let backoff = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(retry))
+            .min(self.max_delay);
+        backoff.mul_f64(0.5 + jitter.clamp(0.0, 1.0) / 2.0)
+    }
+
+    /// Wait before retrying after `error`, or `None` if it isn't worth it.
+    /// A `Retry-After` longer than `max_delay` means the server won't be back
+    /// soon, so the screenshot is better left for the offline queue.
+    pub fn delay_for(&self, error: &NamingError, retry: u32, jitter: f64) -> Option<Duration> {
+        if !error.is_transient() || retry + 1 >= self.max_attempts {
+            return None;
+        }
+        let backoff = self.backoff(retry, jitter);
+        match error {
+            NamingError::HttpStatus {
+                retry_after: Some(seconds),
+                ..
+            } => {
+                let asked = Duration::from_secs(*seconds);
+                (asked <= self.max_delay).then(|| asked.max(backoff))
+            }
+            _ => Some(backoff),
+        }
+    }
+}
+
+/// Runs `attempt` until it succeeds, fails for good, or the policy gives up,
+/// calling `sleep` between tries. Returns the last error.
+///
+/// A `sleep` that returns early on cancellation is fine: the next attempt then
+/// fails with `NamingError::Cancelled`, which is never retried.
+pub fn retry_with<T>(
+    policy: &RetryPolicy,
+    mut attempt: impl FnMut() -> Result<T, NamingError>,
+    mut sleep: impl FnMut(Duration),
+) -> Result<T, NamingError> {
+    let mut retry = 0;
+    loop {
+        let error = match attempt() {
+            Ok(value) => return Ok(value),
+            Err(e) => e,
+        };
+        let Some(delay) = policy.delay_for(&error, retry, jitter()) else {
+            return Err(error);
+        };
+        println!(
+            "🔁 {}; retrying in {:.1}s ({}/{})",
+            error,
+            delay.as_secs_f64(),
+            retry + 1,
+            policy.max_attempts - 1
+        );
+        sleep(delay);
+        retry += 1;
+    }
+}
+
+/// A number in `0.0..=1.0` that differs between calls. `RandomState` is
+/// seeded randomly each time, which is plenty for spreading out retries.
+fn jitter() -> f64 {
+    RandomState::new().build_hasher().finish() as f64 / u64::MAX as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 4,
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(5),
+        }
+    }
+
+    fn network() -> NamingError {
+        NamingError::Network {
+            message: "connection reset".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_backoff_grows_and_is_capped() {
+        let policy = policy();
+        assert_eq!(policy.backoff(0, 1.0), Duration::from_secs(1));
+        assert_eq!(policy.backoff(1, 1.0), Duration::from_secs(2));
+        assert_eq!(policy.backoff(2, 1.0), Duration::from_secs(4));
+        assert_eq!(policy.backoff(3, 1.0), Duration::from_secs(5));
+        assert_eq!(policy.backoff(40, 1.0), Duration::from_secs(5));
+
+        // Jitter only ever shortens the wait, down to half
+        assert_eq!(policy.backoff(1, 0.0), Duration::from_secs(1));
+        assert_eq!(policy.backoff(1, 0.5), Duration::from_millis(1500));
+    }
+
+    #[test]
+    fn test_retries_transient_errors_until_success() {
+        let mut attempts = 0;
+        let mut waits = Vec::new();
+        let result = retry_with(
+            &policy(),
+            || {
+                attempts += 1;
+                match attempts {
+                    1 => Err(network()),
+                    2 => Err(NamingError::HttpStatus {
+                        status: 502,
+                        retry_after: None,
+                    }),
+                    _ => Ok("login page"),
+                }
+            },
+            |wait| waits.push(wait),
+        );
+
+        assert_eq!(result, Ok("login page"));
+        assert_eq!(attempts, 3);
+        assert_eq!(waits.len(), 2);
+        assert!(waits[0] >= Duration::from_millis(500) && waits[0] <= Duration::from_secs(1));
+        assert!(waits[1] >= Duration::from_secs(1) && waits[1] <= Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_gives_up_after_max_attempts() {
+        let mut attempts = 0;
+        let mut waits = 0;
+        let result: Result<(), _> = retry_with(
+            &policy(),
+            || {
+                attempts += 1;
+                Err(network())
+            },
+            |_| waits += 1,
+        );
+
+        assert_eq!(result, Err(network()));
+        assert_eq!(attempts, 4);
+        assert_eq!(waits, 3);
+    }
+
+    #[test]
+    fn test_does_not_retry_lasting_errors() {
+        for error in [
+            NamingError::QuotaExceeded,
+            NamingError::rejected("nope"),
+            NamingError::HttpStatus {
+                status: 400,
+                retry_after: None,
+            },
+        ] {
+            let mut attempts = 0;
+            let result: Result<(), _> = retry_with(
+                &policy(),
+                || {
+                    attempts += 1;
+                    Err(error.clone())
+                },
+                |_| panic!("should not wait"),
+            );
+            assert_eq!(result, Err(error));
+            assert_eq!(attempts, 1);
+        }
+    }
+
+    #[test]
+    fn test_honors_retry_after() {
+        let policy = policy();
+        let busy = |seconds| NamingError::HttpStatus {
+            status: 503,
+            retry_after: Some(seconds),
+        };
+
+        // Waits as long as asked, never less than the backoff
+        assert_eq!(
+            policy.delay_for(&busy(3), 0, 1.0),
+            Some(Duration::from_secs(3))
+        );
+        assert_eq!(
+            policy.delay_for(&busy(0), 2, 1.0),
+            Some(Duration::from_secs(4))
+        );
+        // Longer than we're willing to block a worker for
+        assert_eq!(policy.delay_for(&busy(60), 0, 1.0), None);
+        // No retries left
+        assert_eq!(policy.delay_for(&busy(1), 3, 1.0), None);
+    }
+}
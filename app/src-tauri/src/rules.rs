@@ -0,0 +1,711 @@
+use chrono::{DateTime, Local, NaiveTime};
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+
+use crate::{
+    store::{data_file, load_json, save_json},
+    template::validate_template,
+    user::Tier,
+};
+
+const RULES_FILE: &str = "rules.json";
+
+/// "If all `conditions` hold, do `actions`", checked after a screenshot is named.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Rule {
+    pub name: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    pub conditions: Vec<Condition>,
+    pub actions: Vec<Action>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum Condition {
+    /// Screenshot was taken in this folder or one below it; `~` is allowed
+    SourceFolder {
+        path: String,
+    },
+    /// Case-insensitive regex against the AI-generated name
+    NameMatches {
+        pattern: String,
+    },
+    Size {
+        min_bytes: Option<u64>,
+        max_bytes: Option<u64>,
+    },
+    Dimensions {
+        min_width: Option<u32>,
+        max_width: Option<u32>,
+        min_height: Option<u32>,
+        max_height: Option<u32>,
+    },
+    /// Capture time falls in `start..end`, which may cross midnight
+    TimeOfDay {
+        start: NaiveTime,
+        end: NaiveTime,
+    },
+    Tier {
+        tier: Tier,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Action {
+    /// Move into this folder instead of renaming in place; `~` is allowed
+    Move { folder: String },
+    /// Finder tag on macOS, `user.xdg.tags` on Linux
+    Tag { tag: String },
+    /// Leave the screenshot as it is
+    Skip,
+    /// Name template used instead of the one in settings
+    Template { template: String },
+    /// Desktop notification; the message is a name template
+    Notify { message: String },
+}
+
+/// What's known about a screenshot when rules run.
+pub struct RuleInput<'a> {
+    pub path: &'a Path,
+    pub name: &'a str,
+    pub size: u64,
+    /// Width and height, if the image header could be read
+    pub dimensions: Option<(u32, u32)>,
+    pub captured_at: DateTime<Local>,
+    pub tier: &'a Tier,
+}
+
+/// Combined effect of every matching rule. Move and template come from the
+/// first rule that sets them; tags and notifications add up.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RuleOutcome {
+    pub matched: Vec<String>,
+    pub skip: bool,
+    pub move_to: Option<PathBuf>,
+    pub template: Option<String>,
+    pub tags: Vec<String>,
+    pub notifications: Vec<String>,
+}
+
+pub fn evaluate_rules(rules: &[Rule], input: &RuleInput) -> RuleOutcome {
+    let mut outcome = RuleOutcome::default();
+
+    for rule in rules.iter().filter(|r| r.enabled) {
+        if !rule.conditions.iter().all(|c| condition_matches(c, input)) {
+            continue;
+        }
+        outcome.matched.push(rule.name.clone());
+
+        for action in &rule.actions {
+            match action {
+                Action::Move { folder } => {
+                    outcome.move_to.get_or_insert_with(|| expand_home(folder));
+                }
+                Action::Tag { tag } => {
+                    if !outcome.tags.contains(tag) {
+                        outcome.tags.push(tag.clone());
+                    }
+                }
+                Action::Skip => outcome.skip = true,
+                Action::Template { template } => {
+                    outcome.template.get_or_insert_with(|| template.clone());
+                }
+                Action::Notify { message } => outcome.notifications.push(message.clone()),
+            }
+        }
+    }
+    outcome
+}
+
+fn condition_matches(condition: &Condition, input: &RuleInput) -> bool {
+    match condition {
+        Condition::SourceFolder { path } => input
+            .path
+            .parent()
+            .is_some_and(|parent| parent.starts_with(expand_home(path))),
+        // Patterns are checked by validate_rules, so this only fails for
+        // rules files edited by hand while the app runs
+        Condition::NameMatches { pattern } => {
+            name_regex(pattern).is_ok_and(|regex| regex.is_match(input.name))
+        }
+        Condition::Size {
+            min_bytes,
+            max_bytes,
+        } => in_range(input.size, *min_bytes, *max_bytes),
+        Condition::Dimensions {
+            min_width,
+            max_width,
+            min_height,
+            max_height,
+        } => input.dimensions.is_some_and(|(width, height)| {
+            in_range(width, *min_width, *max_width) && in_range(height, *min_height, *max_height)
+        }),
+        Condition::TimeOfDay { start, end } => {
+            let time = input.captured_at.time();
+            if start <= end {
+                *start <= time && time < *end
+            } else {
+                time >= *start || time < *end
+            }
+        }
+        Condition::Tier { tier } => tier == input.tier,
+    }
+}
+
+fn in_range<T: PartialOrd>(value: T, min: Option<T>, max: Option<T>) -> bool {
+    min.is_none_or(|min| value >= min) && max.is_none_or(|max| value <= max)
+}
+
+fn name_regex(pattern: &str) -> Result<Regex, regex::Error> {
+    RegexBuilder::new(pattern).case_insensitive(true).build()
+}
+
+/// Expands a leading `~` to the home directory.
+pub fn expand_home(path: &str) -> PathBuf {
+    match path.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => match dirs::home_dir() {
+            Some(home) => home.join(rest.trim_start_matches('/')),
+            None => PathBuf::from(path),
+        },
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Checks every rule, naming the rule and the problem in the error.
+pub fn validate_rules(rules: &[Rule]) -> Result<(), String> {
+    for (i, rule) in rules.iter().enumerate() {
+        let label = if rule.name.trim().is_empty() {
+            format!("Rule {}", i + 1)
+        } else {
+            format!("Rule '{}'", rule.name)
+        };
+        validate_rule(rule).map_err(|e| format!("{}: {}", label, e))?;
+    }
+    Ok(())
+}
+
+fn validate_rule(rule: &Rule) -> Result<(), String> {
+    if rule.name.trim().is_empty() {
+        return Err("needs a name".to_string());
+    }
+    if rule.actions.is_empty() {
+        return Err("needs at least one action".to_string());
+    }
+
+    for condition in &rule.conditions {
+        match condition {
+            Condition::SourceFolder { path } => {
+                if !expand_home(path).is_absolute() {
+                    return Err(format!("source folder must be an absolute path: {}", path));
+                }
+            }
+            Condition::NameMatches { pattern } => {
+                name_regex(pattern)
+                    .map_err(|e| format!("invalid name pattern '{}': {}", pattern, e))?;
+            }
+            Condition::Size {
+                min_bytes,
+                max_bytes,
+            } => {
+                if min_bytes.is_none() && max_bytes.is_none() {
+                    return Err("size condition needs a minimum or a maximum".to_string());
+                }
+                check_range("size", *min_bytes, *max_bytes)?;
+            }
+            Condition::Dimensions {
+                min_width,
+                max_width,
+                min_height,
+                max_height,
+            } => {
+                if [min_width, max_width, min_height, max_height]
+                    .iter()
+                    .all(|bound| bound.is_none())
+                {
+                    return Err("dimensions condition needs at least one bound".to_string());
+                }
+                check_range("width", *min_width, *max_width)?;
+                check_range("height", *min_height, *max_height)?;
+            }
+            Condition::TimeOfDay { start, end } => {
+                if start == end {
+                    return Err("time of day start and end can't be the same".to_string());
+                }
+            }
+            Condition::Tier { .. } => {}
+        }
+    }
+
+    for action in &rule.actions {
+        match action {
+            Action::Move { folder } => {
+                if !expand_home(folder).is_absolute() {
+                    return Err(format!("move folder must be an absolute path: {}", folder));
+                }
+            }
+            Action::Tag { tag } => {
+                if tag.trim().is_empty() || tag.contains(',') {
+                    return Err(format!("invalid tag '{}'", tag));
+                }
+            }
+            Action::Skip => {}
+            Action::Template { template } => {
+                validate_template(template).map_err(|e| format!("template: {}", e))?
+            }
+            Action::Notify { message } => {
+                validate_template(message).map_err(|e| format!("notify message: {}", e))?
+            }
+        }
+    }
+    Ok(())
+}
+
+fn check_range<T: PartialOrd + Copy>(
+    what: &str,
+    min: Option<T>,
+    max: Option<T>,
+) -> Result<(), String> {
+    match (min, max) {
+        (Some(min), Some(max)) if min > max => {
+            Err(format!("{} minimum is larger than the maximum", what))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Loads `rules.json`, dropping the whole set if it doesn't validate so a bad
+/// hand edit can't half-apply.
+pub fn load_rules(app_handle: &AppHandle) -> Vec<Rule> {
+    let rules: Vec<Rule> = match data_file(app_handle, RULES_FILE) {
+        Some(path) => load_json(&path),
+        None => Vec::new(),
+    };
+    if let Err(e) = validate_rules(&rules) {
+        eprintln!("❌ Saved rules are invalid, ignoring them: {}", e);
+        return Vec::new();
+    }
+    rules
+}
+
+pub fn save_rules(app_handle: &AppHandle, rules: &[Rule]) {
+    let Some(path) = data_file(app_handle, RULES_FILE) else {
+        return;
+    };
+    if let Err(e) = save_json(&path, &rules) {
+        eprintln!("❌ Failed to save rules: {}", e);
+    }
+}
+
+/// Adds `tags` to the file where the OS has a place for them, keeping the tags
+/// it already has.
+#[cfg(target_os = "macos")]
+pub fn apply_tags(path: &Path, tags: &[String]) -> Result<(), String> {
+    if tags.is_empty() {
+        return Ok(());
+    }
+    // Finder reads tags from an XML or binary plist array in this attribute
+    let name = c"com.apple.metadata:_kMDItemUserTags";
+    let existing: Vec<String> = match read_xattr(path, name)? {
+        Some(bytes) => plist::from_bytes(&bytes)
+            .map_err(|e| format!("Can't read the file's existing tags: {}", e))?,
+        None => Vec::new(),
+    };
+
+    let mut value = Vec::new();
+    plist::to_writer_binary(&mut value, &merge_tags(existing, tags)).map_err(|e| e.to_string())?;
+    write_xattr(path, name, &value)
+}
+
+#[cfg(target_os = "linux")]
+pub fn apply_tags(path: &Path, tags: &[String]) -> Result<(), String> {
+    if tags.is_empty() {
+        return Ok(());
+    }
+    // Comma-separated, as read by file managers that follow the xdg convention
+    let name = c"user.xdg.tags";
+    let existing: Vec<String> = match read_xattr(path, name)? {
+        Some(bytes) => String::from_utf8_lossy(&bytes)
+            .split(',')
+            .filter(|tag| !tag.is_empty())
+            .map(str::to_string)
+            .collect(),
+        None => Vec::new(),
+    };
+
+    let value = merge_tags(existing, tags).join(",");
+    write_xattr(path, name, value.as_bytes())
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+pub fn apply_tags(_path: &Path, _tags: &[String]) -> Result<(), String> {
+    Ok(())
+}
+
+/// Adds `tags` after the `existing` ones, skipping any the file already has.
+/// Finder keeps a tag's color after a newline, e.g. `Work\n6`, and treats
+/// names that differ only in case as the same tag.
+fn merge_tags(mut existing: Vec<String>, tags: &[String]) -> Vec<String> {
+    fn tag_name(tag: &str) -> String {
+        tag.split('\n').next().unwrap_or(tag).to_lowercase()
+    }
+
+    for tag in tags {
+        if !existing.iter().any(|e| tag_name(e) == tag_name(tag)) {
+            existing.push(tag.clone());
+        }
+    }
+    existing
+}
+
+/// The attribute's value, or `None` if the file doesn't have it.
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+fn read_xattr(path: &Path, name: &std::ffi::CStr) -> Result<Option<Vec<u8>>, String> {
+    use std::{ffi::CString, os::unix::ffi::OsStrExt, ptr};
+
+    #[cfg(target_os = "macos")]
+    const MISSING: i32 = libc::ENOATTR;
+    #[cfg(target_os = "linux")]
+    const MISSING: i32 = libc::ENODATA;
+
+    let c_path = CString::new(path.as_os_str().as_bytes()).map_err(|e| e.to_string())?;
+    let get = |buf: *mut libc::c_void, size: usize| unsafe {
+        #[cfg(target_os = "macos")]
+        let res = libc::getxattr(c_path.as_ptr(), name.as_ptr(), buf, size, 0, 0);
+        #[cfg(target_os = "linux")]
+        let res = libc::getxattr(c_path.as_ptr(), name.as_ptr(), buf, size);
+        res
+    };
+
+    // Ask for the size first, then read the value
+    let size = get(ptr::null_mut(), 0);
+    if size < 0 {
+        let err = std::io::Error::last_os_error();
+        if err.raw_os_error() == Some(MISSING) {
+            return Ok(None);
+        }
+        return Err(err.to_string());
+    }
+    let mut value = vec![0u8; size as usize];
+    let read = get(value.as_mut_ptr() as *mut libc::c_void, value.len());
+    if read < 0 {
+        return Err(std::io::Error::last_os_error().to_string());
+    }
+    value.truncate(read as usize);
+    Ok(Some(value))
+}
+
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+fn write_xattr(path: &Path, name: &std::ffi::CStr, value: &[u8]) -> Result<(), String> {
+    use std::{ffi::CString, os::unix::ffi::OsStrExt};
+
+    let c_path = CString::new(path.as_os_str().as_bytes()).map_err(|e| e.to_string())?;
+    let res = unsafe {
+        #[cfg(target_os = "macos")]
+        let res = libc::setxattr(
+            c_path.as_ptr(),
+            name.as_ptr(),
+            value.as_ptr() as *const libc::c_void,
+            value.len(),
+            0,
+            0,
+        );
+        #[cfg(target_os = "linux")]
+        let res = libc::setxattr(
+            c_path.as_ptr(),
+            name.as_ptr(),
+            value.as_ptr() as *const libc::c_void,
+            value.len(),
+            0,
+        );
+        res
+    };
+    if res != 0 {
+        return Err(std::io::Error::last_os_error().to_string());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::user::Tier;
+    use chrono::{Local, NaiveTime, TimeZone};
+    use std::path::{Path, PathBuf};
+
+    fn input<'a>(path: &'a Path, name: &'a str, tier: &'a Tier) -> RuleInput<'a> {
+        RuleInput {
+            path,
+            name,
+            size: 250_000,
+            dimensions: Some((1440, 900)),
+            captured_at: Local.with_ymd_and_hms(2026, 10, 3, 22, 15, 0).unwrap(),
+            tier,
+        }
+    }
+
+    fn rule(name: &str, conditions: Vec<Condition>, actions: Vec<Action>) -> Rule {
+        Rule {
+            name: name.to_string(),
+            enabled: true,
+            conditions,
+            actions,
+        }
+    }
+
+    fn tag(tag: &str) -> Action {
+        Action::Tag {
+            tag: tag.to_string(),
+        }
+    }
+
+    fn time(h: u32, m: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(h, m, 0).unwrap()
+    }
+
+    #[test]
+    fn test_conditions() {
+        let path = Path::new("/Users/me/Desktop/work/shot.png");
+        let tier = Tier::Pro;
+        let shot = input(path, "Invoice March 2026", &tier);
+
+        let matches = |condition: Condition| {
+            let rules = [rule("r", vec![condition], vec![tag("hit")])];
+            !evaluate_rules(&rules, &shot).matched.is_empty()
+        };
+
+        assert!(matches(Condition::SourceFolder {
+            path: "/Users/me/Desktop".to_string()
+        }));
+        assert!(!matches(Condition::SourceFolder {
+            path: "/Users/me/Downloads".to_string()
+        }));
+        assert!(matches(Condition::NameMatches {
+            pattern: "^invoice".to_string()
+        }));
+        assert!(!matches(Condition::NameMatches {
+            pattern: "receipt".to_string()
+        }));
+        assert!(matches(Condition::Size {
+            min_bytes: Some(100_000),
+            max_bytes: None
+        }));
+        assert!(!matches(Condition::Size {
+            min_bytes: None,
+            max_bytes: Some(100_000)
+        }));
+        assert!(matches(Condition::Dimensions {
+            min_width: Some(1280),
+            max_width: None,
+            min_height: None,
+            max_height: Some(1000)
+        }));
+        assert!(!matches(Condition::Dimensions {
+            min_width: Some(2000),
+            max_width: None,
+            min_height: None,
+            max_height: None
+        }));
+        assert!(matches(Condition::Tier { tier: Tier::Pro }));
+        assert!(!matches(Condition::Tier { tier: Tier::Free }));
+    }
+
+    #[test]
+    fn test_time_of_day_crosses_midnight() {
+        let path = Path::new("/tmp/shot.png");
+        let tier = Tier::Free;
+        let late = input(path, "shot", &tier);
+        let at = |start, end| {
+            let rules = [rule(
+                "r",
+                vec![Condition::TimeOfDay { start, end }],
+                vec![Action::Skip],
+            )];
+            evaluate_rules(&rules, &late).skip
+        };
+
+        assert!(at(time(22, 0), time(6, 0)));
+        assert!(at(time(9, 0), time(23, 0)));
+        assert!(!at(time(9, 0), time(17, 0)));
+        assert!(!at(time(23, 0), time(6, 0)));
+    }
+
+    #[test]
+    fn test_actions_combine_across_rules() {
+        let path = Path::new("/tmp/shot.png");
+        let tier = Tier::Free;
+        let shot = input(path, "shot", &tier);
+        let mut rules = vec![
+            rule(
+                "first",
+                vec![],
+                vec![
+                    Action::Move {
+                        folder: "/Users/me/Receipts".to_string(),
+                    },
+                    tag("finance"),
+                    Action::Notify {
+                        message: "Filed {name}".to_string(),
+                    },
+                ],
+            ),
+            rule("disabled", vec![], vec![Action::Skip]),
+            rule(
+                "second",
+                vec![],
+                vec![
+                    Action::Move {
+                        folder: "/Users/me/Other".to_string(),
+                    },
+                    tag("finance"),
+                    tag("2026"),
+                    Action::Template {
+                        template: "{date}_{name}".to_string(),
+                    },
+                ],
+            ),
+        ];
+        rules[1].enabled = false;
+
+        let outcome = evaluate_rules(&rules, &shot);
+        assert_eq!(outcome.matched, vec!["first", "second"]);
+        assert!(!outcome.skip);
+        assert_eq!(outcome.move_to, Some(PathBuf::from("/Users/me/Receipts")));
+        assert_eq!(outcome.template.as_deref(), Some("{date}_{name}"));
+        assert_eq!(outcome.tags, vec!["finance", "2026"]);
+        assert_eq!(outcome.notifications, vec!["Filed {name}"]);
+    }
+
+    #[test]
+    fn test_validation() {
+        let valid = rule(
+            "Receipts",
+            vec![Condition::NameMatches {
+                pattern: "receipt|invoice".to_string(),
+            }],
+            vec![tag("finance")],
+        );
+        assert!(validate_rules(std::slice::from_ref(&valid)).is_ok());
+
+        let bad_pattern = rule(
+            "Broken",
+            vec![Condition::NameMatches {
+                pattern: "(".to_string(),
+            }],
+            vec![tag("x")],
+        );
+        let err = validate_rules(&[valid, bad_pattern]).unwrap_err();
+        assert!(err.starts_with("Rule 'Broken'"), "{}", err);
+
+        let no_actions = rule("Empty", vec![], vec![]);
+        assert!(validate_rules(&[no_actions]).is_err());
+
+        let relative_move = rule(
+            "Move",
+            vec![],
+            vec![Action::Move {
+                folder: "Receipts".to_string(),
+            }],
+        );
+        assert!(validate_rules(&[relative_move]).is_err());
+
+        let inverted_size = rule(
+            "Size",
+            vec![Condition::Size {
+                min_bytes: Some(10),
+                max_bytes: Some(1),
+            }],
+            vec![Action::Skip],
+        );
+        assert!(validate_rules(&[inverted_size]).is_err());
+
+        let bad_template = rule(
+            "Template",
+            vec![],
+            vec![Action::Template {
+                template: "{nope}".to_string(),
+            }],
+        );
+        assert!(validate_rules(&[bad_template]).is_err());
+    }
+
+    #[test]
+    fn test_json_shape() {
+        let json = r#"{
+        "name": "Big ones",
+        "conditions": [
+            { "type": "size", "minBytes": 1000000 },
+            { "type": "timeOfDay", "start": "22:00:00", "end": "06:00:00" }
+        ],
+        "actions": [
+            { "type": "move", "folder": "/Users/me/Large" },
+            { "type": "skip" }
+        ]
+    }"#;
+        let rule: Rule = serde_json::from_str(json).unwrap();
+        assert!(rule.enabled);
+        assert_eq!(
+            rule.conditions[0],
+            Condition::Size {
+                min_bytes: Some(1_000_000),
+                max_bytes: None
+            }
+        );
+        assert_eq!(rule.actions[1], Action::Skip);
+    }
+
+    #[test]
+    fn test_merge_tags() {
+        let existing = vec!["Work\n6".to_string(), "receipts".to_string()];
+        let tags = vec![
+            "work".to_string(),
+            "Receipts".to_string(),
+            "late".to_string(),
+        ];
+        assert_eq!(
+            merge_tags(existing, &tags),
+            vec!["Work\n6", "receipts", "late"]
+        );
+        assert_eq!(
+            merge_tags(Vec::new(), &tags),
+            vec!["work", "Receipts", "late"]
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_apply_tags_keeps_existing_tags() {
+        let dir = PathBuf::from("tests/assets/rules_tags");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("shot.png");
+        std::fs::write(&path, b"png").unwrap();
+
+        let tagged = |tags: &[&str]| {
+            let tags: Vec<String> = tags.iter().map(|t| t.to_string()).collect();
+            apply_tags(&path, &tags)
+        };
+        if tagged(&["receipts"]).is_err() {
+            // The filesystem doesn't support user xattrs
+            let _ = std::fs::remove_dir_all(&dir);
+            return;
+        }
+        tagged(&["late", "Receipts"]).unwrap();
+        let value = read_xattr(&path, c"user.xdg.tags").unwrap().unwrap();
+        assert_eq!(String::from_utf8(value).unwrap(), "receipts,late");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
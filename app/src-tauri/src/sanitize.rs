@@ -0,0 +1,469 @@
+use std::path::Path;
+use unicode_normalization::UnicodeNormalization;
+
+use crate::rename::MAX_SUFFIX_LEN;
+
+/// Soft cap so names stay readable in Finder and Explorer, well under every
+/// filesystem's hard limit.
+pub const MAX_NAME_CHARS: usize = 100;
+
+/// Filesystem family of the folder a screenshot is renamed in. Each has its own
+/// set of forbidden characters and its own way of counting name length.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilesystemKind {
+    /// FAT32 / VFAT long names
+    Fat,
+    ExFat,
+    Ntfs,
+    /// APFS and HFS+
+    Apfs,
+    /// ext4 and the other Unix filesystems (btrfs, xfs, tmpfs, ...)
+    Ext4,
+}
+
+impl FilesystemKind {
+    fn is_windows_family(self) -> bool {
+        matches!(
+            self,
+            FilesystemKind::Fat | FilesystemKind::ExFat | FilesystemKind::Ntfs
+        )
+    }
+
+    fn is_forbidden(self, c: char) -> bool {
+        // Path separators are never allowed through, whatever the filesystem
+        if c.is_control() || c == '/' || c == '\\' {
+            return true;
+        }
+        match self {
+            FilesystemKind::Fat | FilesystemKind::ExFat | FilesystemKind::Ntfs => {
+                matches!(c, '<' | '>' | ':' | '"' | '|' | '?' | '*')
+            }
+            // Finder shows ':' as '/', and the shell APIs reject it
+            FilesystemKind::Apfs => c == ':',
+            FilesystemKind::Ext4 => false,
+        }
+    }
+
+    /// Length of a full file name in the units the filesystem limits.
+    fn name_len(self, name: &str) -> usize {
+        match self {
+            // 255 UTF-16 code units
+            FilesystemKind::Fat
+            | FilesystemKind::ExFat
+            | FilesystemKind::Ntfs
+            | FilesystemKind::Apfs => name.encode_utf16().count(),
+            // 255 bytes
+            FilesystemKind::Ext4 => name.len(),
+        }
+    }
+}
+
+/// Hard limit shared by all supported filesystems, in their own units.
+const MAX_NAME_LEN: usize = 255;
+
+/// Device names Windows reserves in every folder, with or without an extension.
+const WINDOWS_RESERVED: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Turns a suggested name into a safe file stem for `fs`, leaving room for
+/// `.ext` and a conflict suffix. Errors when nothing usable is left.
+pub fn sanitize_file_name(name: &str, ext: &str, fs: FilesystemKind) -> Result<String, String> {
+    let normalized: String = name.nfc().collect();
+
+    // Forbidden characters become '-'; whitespace runs collapse to one space
+    let mut cleaned = String::with_capacity(normalized.len());
+    for c in normalized.chars() {
+        let c = if c.is_whitespace() {
+            ' '
+        } else if fs.is_forbidden(c) {
+            '-'
+        } else {
+            c
+        };
+        let repeated = matches!(c, ' ' | '-') && cleaned.ends_with(c);
+        if !repeated {
+            cleaned.push(c);
+        }
+    }
+
+    // Leading dots hide the file; trailing dots and spaces are dropped by
+    // Windows; dashes at either end are leftovers of replaced characters
+    let mut stem = trim_edges(&cleaned).to_string();
+
+    let budget = MAX_NAME_LEN.saturating_sub(fs.name_len(ext) + 1 + MAX_SUFFIX_LEN);
+    stem = truncate_on_word_boundary(&stem, fs, budget);
+
+    if stem.is_empty() {
+        return Err(format!(
+            "'{}' has no usable characters for a file name",
+            name
+        ));
+    }
+
+    if fs.is_windows_family() && is_windows_reserved(&stem) {
+        stem.push('_');
+    }
+
+    Ok(stem)
+}
+
+fn trim_edges(name: &str) -> &str {
+    name.trim_matches(['.', ' ', '-'])
+}
+
+/// `CON`, `con`, `Com1` and `CON.tar` are all reserved; `CONSOLE` is not.
+fn is_windows_reserved(stem: &str) -> bool {
+    let base = stem.split('.').next().unwrap_or(stem).trim_end();
+    WINDOWS_RESERVED
+        .iter()
+        .any(|reserved| reserved.eq_ignore_ascii_case(base))
+}
+
+/// Cuts `name` down to `MAX_NAME_CHARS` and to `budget` filesystem units,
+/// preferring to end at a word boundary.
+fn truncate_on_word_boundary(name: &str, fs: FilesystemKind, budget: usize) -> String {
+    let mut end = 0;
+    for (chars, (i, c)) in name.char_indices().enumerate() {
+        let next = i + c.len_utf8();
+        if chars >= MAX_NAME_CHARS || fs.name_len(&name[..next]) > budget {
+            break;
+        }
+        end = next;
+    }
+    if end == name.len() {
+        return name.to_string();
+    }
+
+    // Back up to the last separator, unless that would throw away most of the name
+    let cut = &name[..end];
+    let boundary = cut
+        .rfind([' ', '-', '_'])
+        .filter(|&i| i >= end / 2)
+        .unwrap_or(end);
+    trim_edges(cut[..boundary].trim_end_matches('_')).to_string()
+}
+
+/// Best guess at the filesystem `dir` lives on. Falls back to the platform's
+/// usual filesystem when it can't be determined.
+#[cfg(target_os = "linux")]
+pub fn detect_filesystem(dir: &Path) -> FilesystemKind {
+    use std::{ffi::CString, os::unix::ffi::OsStrExt};
+
+    const MSDOS_SUPER_MAGIC: i64 = 0x4d44;
+    const EXFAT_SUPER_MAGIC: i64 = 0x2011_bab0;
+    const NTFS_SB_MAGIC: i64 = 0x5346_544e;
+    const FUSE_SUPER_MAGIC: i64 = 0x6573_5546;
+
+    let Ok(path) = CString::new(dir.as_os_str().as_bytes()) else {
+        return FilesystemKind::Ext4;
+    };
+    let mut stat: libc::statfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statfs(path.as_ptr(), &mut stat) } != 0 {
+        return FilesystemKind::Ext4;
+    }
+
+    #[allow(clippy::unnecessary_cast)]
+    match stat.f_type as i64 {
+        MSDOS_SUPER_MAGIC => FilesystemKind::Fat,
+        EXFAT_SUPER_MAGIC => FilesystemKind::ExFat,
+        NTFS_SB_MAGIC => FilesystemKind::Ntfs,
+        // ntfs-3g and exfat-fuse; Windows rules are the safe superset
+        FUSE_SUPER_MAGIC => FilesystemKind::Ntfs,
+        _ => FilesystemKind::Ext4,
+    }
+}
+
+#[cfg(target_os = "macos")]
+pub fn detect_filesystem(dir: &Path) -> FilesystemKind {
+    use std::{
+        ffi::{CStr, CString},
+        os::unix::ffi::OsStrExt,
+    };
+
+    let Ok(path) = CString::new(dir.as_os_str().as_bytes()) else {
+        return FilesystemKind::Apfs;
+    };
+    let mut stat: libc::statfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statfs(path.as_ptr(), &mut stat) } != 0 {
+        return FilesystemKind::Apfs;
+    }
+
+    let name = unsafe { CStr::from_ptr(stat.f_fstypename.as_ptr()) };
+    match name.to_bytes() {
+        b"msdos" => FilesystemKind::Fat,
+        b"exfat" => FilesystemKind::ExFat,
+        b"ntfs" => FilesystemKind::Ntfs,
+        _ => FilesystemKind::Apfs,
+    }
+}
+
+/// FAT and exFAT drives on Windows share NTFS's character and length rules.
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub fn detect_filesystem(_dir: &Path) -> FilesystemKind {
+    FilesystemKind::Ntfs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL_FILESYSTEMS: [FilesystemKind; 5] = [
+        FilesystemKind::Fat,
+        FilesystemKind::ExFat,
+        FilesystemKind::Ntfs,
+        FilesystemKind::Apfs,
+        FilesystemKind::Ext4,
+    ];
+
+    fn sanitize(name: &str, fs: FilesystemKind) -> String {
+        sanitize_file_name(name, "png", fs).unwrap()
+    }
+
+    #[test]
+    fn test_plain_names_untouched() {
+        for fs in ALL_FILESYSTEMS {
+            assert_eq!(sanitize("login-page", fs), "login-page");
+            assert_eq!(
+                sanitize("Quarterly report draft", fs),
+                "Quarterly report draft"
+            );
+            assert_eq!(sanitize("v1.2 release notes", fs), "v1.2 release notes");
+        }
+    }
+
+    #[test]
+    fn test_path_separators_cannot_escape_folder() {
+        for fs in ALL_FILESYSTEMS {
+            assert_eq!(sanitize("../../etc/passwd", fs), "etc-passwd");
+            assert_eq!(sanitize("/absolute/path", fs), "absolute-path");
+            assert_eq!(sanitize("..\\..\\Windows\\win.ini", fs), "Windows-win.ini");
+            assert_eq!(sanitize("a/b\\c", fs), "a-b-c");
+        }
+    }
+
+    #[test]
+    fn test_dot_names_rejected() {
+        for fs in ALL_FILESYSTEMS {
+            assert!(sanitize_file_name(".", "png", fs).is_err());
+            assert!(sanitize_file_name("..", "png", fs).is_err());
+            assert!(sanitize_file_name("...", "png", fs).is_err());
+            assert!(sanitize_file_name("../", "png", fs).is_err());
+        }
+    }
+
+    #[test]
+    fn test_empty_and_blank_names_rejected() {
+        for fs in ALL_FILESYSTEMS {
+            assert!(sanitize_file_name("", "png", fs).is_err());
+            assert!(sanitize_file_name("   ", "png", fs).is_err());
+            assert!(sanitize_file_name("\n\t", "png", fs).is_err());
+            assert!(sanitize_file_name("///", "png", fs).is_err());
+        }
+    }
+
+    #[test]
+    fn test_nul_and_control_characters() {
+        for fs in ALL_FILESYSTEMS {
+            assert_eq!(sanitize("login\0page", fs), "login-page");
+            assert_eq!(sanitize("bell\u{7}char", fs), "bell-char");
+            assert_eq!(sanitize("del\u{7f}char", fs), "del-char");
+            assert!(sanitize_file_name("\0", "png", fs).is_err());
+        }
+    }
+
+    #[test]
+    fn test_whitespace_collapsed() {
+        for fs in ALL_FILESYSTEMS {
+            assert_eq!(sanitize("  login   page  ", fs), "login page");
+            assert_eq!(sanitize("login\npage", fs), "login page");
+            assert_eq!(sanitize("login\tpage", fs), "login page");
+            assert_eq!(sanitize("login\u{a0}page", fs), "login page");
+        }
+    }
+
+    #[test]
+    fn test_leading_and_trailing_dots() {
+        for fs in ALL_FILESYSTEMS {
+            assert_eq!(sanitize(".hidden", fs), "hidden");
+            assert_eq!(sanitize("login page.", fs), "login page");
+            assert_eq!(sanitize("login page...", fs), "login page");
+            assert_eq!(sanitize("login page . . ", fs), "login page");
+            assert_eq!(sanitize("-dashed-", fs), "dashed");
+        }
+    }
+
+    #[test]
+    fn test_windows_forbidden_characters() {
+        for fs in [
+            FilesystemKind::Fat,
+            FilesystemKind::ExFat,
+            FilesystemKind::Ntfs,
+        ] {
+            assert_eq!(sanitize("what? why*", fs), "what- why");
+            assert_eq!(sanitize("a<b>c", fs), "a-b-c");
+            assert_eq!(
+                sanitize("Error: \"file\" | pipe", fs),
+                "Error- -file- - pipe"
+            );
+            assert_eq!(sanitize("10:30 meeting", fs), "10-30 meeting");
+        }
+    }
+
+    #[test]
+    fn test_unix_keeps_windows_characters() {
+        assert_eq!(sanitize("what? why*", FilesystemKind::Ext4), "what? why*");
+        assert_eq!(sanitize("a<b>c", FilesystemKind::Ext4), "a<b>c");
+        assert_eq!(
+            sanitize("10:30 meeting", FilesystemKind::Ext4),
+            "10:30 meeting"
+        );
+    }
+
+    #[test]
+    fn test_colon_on_apfs() {
+        assert_eq!(
+            sanitize("10:30 meeting", FilesystemKind::Apfs),
+            "10-30 meeting"
+        );
+        assert_eq!(sanitize("what? why*", FilesystemKind::Apfs), "what? why*");
+    }
+
+    #[test]
+    fn test_repeated_replacements_collapsed() {
+        assert_eq!(sanitize("a???b", FilesystemKind::Ntfs), "a-b");
+        assert_eq!(sanitize("a//b", FilesystemKind::Ext4), "a-b");
+        assert_eq!(sanitize("a - b", FilesystemKind::Ext4), "a - b");
+    }
+
+    #[test]
+    fn test_windows_reserved_names() {
+        for fs in [
+            FilesystemKind::Fat,
+            FilesystemKind::ExFat,
+            FilesystemKind::Ntfs,
+        ] {
+            assert_eq!(sanitize("CON", fs), "CON_");
+            assert_eq!(sanitize("con", fs), "con_");
+            assert_eq!(sanitize("Aux", fs), "Aux_");
+            assert_eq!(sanitize("NUL", fs), "NUL_");
+            assert_eq!(sanitize("PRN", fs), "PRN_");
+            assert_eq!(sanitize("com1", fs), "com1_");
+            assert_eq!(sanitize("LPT9", fs), "LPT9_");
+            assert_eq!(sanitize("CON.tar", fs), "CON.tar_");
+            assert_eq!(sanitize("CON.", fs), "CON_");
+            assert_eq!(sanitize("CON ", fs), "CON_");
+            // Only the exact device names are reserved
+            assert_eq!(sanitize("CONSOLE", fs), "CONSOLE");
+            assert_eq!(sanitize("com10", fs), "com10");
+            assert_eq!(sanitize("aux-cable", fs), "aux-cable");
+        }
+    }
+
+    #[test]
+    fn test_reserved_names_fine_on_unix() {
+        assert_eq!(sanitize("CON", FilesystemKind::Ext4), "CON");
+        assert_eq!(sanitize("aux", FilesystemKind::Apfs), "aux");
+    }
+
+    #[test]
+    fn test_unicode_normalized_to_nfc() {
+        // "é" as e + combining acute becomes the single precomposed character
+        let decomposed = "cafe\u{301} menu";
+        for fs in ALL_FILESYSTEMS {
+            assert_eq!(sanitize(decomposed, fs), "caf\u{e9} menu");
+        }
+        assert_eq!(sanitize("ångström", FilesystemKind::Ext4), "ångström");
+        assert_eq!(
+            sanitize("スクリーンショット", FilesystemKind::Ntfs),
+            "スクリーンショット"
+        );
+    }
+
+    #[test]
+    fn test_truncates_long_names_on_word_boundary() {
+        let long = "quarterly revenue dashboard with regional breakdown ".repeat(6);
+        assert_eq!(long.chars().count(), 312);
+
+        for fs in ALL_FILESYSTEMS {
+            let name = sanitize(&long, fs);
+            assert!(name.chars().count() <= MAX_NAME_CHARS, "Got {}", name);
+            assert!(long.starts_with(&name));
+            // Ends on a whole word
+            assert!(
+                long[name.len()..].starts_with(' '),
+                "Cut mid-word: {}",
+                name
+            );
+            assert!(!name.ends_with(' '));
+        }
+    }
+
+    #[test]
+    fn test_truncates_kebab_case_on_dash() {
+        let long = "very-long-kebab-case-name-from-the-server-".repeat(8);
+        let name = sanitize(&long, FilesystemKind::Ext4);
+        assert!(name.chars().count() <= MAX_NAME_CHARS);
+        assert!(!name.ends_with('-'));
+        assert!(
+            long[name.len()..].starts_with('-'),
+            "Cut mid-word: {}",
+            name
+        );
+    }
+
+    #[test]
+    fn test_truncates_without_separators() {
+        let long = "a".repeat(300);
+        for fs in ALL_FILESYSTEMS {
+            assert_eq!(sanitize(&long, fs), "a".repeat(MAX_NAME_CHARS));
+        }
+    }
+
+    #[test]
+    fn test_byte_limit_on_ext4() {
+        // 4-byte characters hit ext4's 255-byte limit before the character cap
+        let emoji = "😀".repeat(MAX_NAME_CHARS);
+        let name = sanitize(&emoji, FilesystemKind::Ext4);
+        assert!(
+            name.len() + MAX_SUFFIX_LEN + ".png".len() <= 255,
+            "{} bytes",
+            name.len()
+        );
+        assert_eq!(name.chars().count(), (255 - 4 - MAX_SUFFIX_LEN) / 4);
+    }
+
+    #[test]
+    fn test_utf16_limit_on_windows() {
+        // Each emoji is two UTF-16 units
+        let emoji = "😀".repeat(MAX_NAME_CHARS * 2);
+        for fs in [
+            FilesystemKind::Fat,
+            FilesystemKind::ExFat,
+            FilesystemKind::Ntfs,
+        ] {
+            let name = sanitize(&emoji, fs);
+            assert_eq!(name.chars().count(), MAX_NAME_CHARS);
+            assert!(name.encode_utf16().count() + MAX_SUFFIX_LEN + ".png".len() <= 255);
+        }
+    }
+
+    #[test]
+    fn test_never_splits_characters() {
+        let long = "é".repeat(300);
+        for fs in ALL_FILESYSTEMS {
+            let name = sanitize(&long, fs);
+            assert!(name.chars().all(|c| c == 'é'));
+            assert!(!name.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_long_extension_reduces_budget() {
+        let long = "a".repeat(300);
+        let ext = "x".repeat(200);
+        let name = sanitize_file_name(&long, &ext, FilesystemKind::Ext4).unwrap();
+        assert_eq!(name.len() + MAX_SUFFIX_LEN + 1 + ext.len(), 255);
+    }
+}
@@ -0,0 +1,97 @@
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::{
+    naming::NamingSettings,
+    organize::OrganizeSettings,
+    pause::{PauseMode, QuietHours},
+    rename::ConflictStrategy,
+    store::{data_file, load_json, save_json},
+    template::{validate_template, CaseStyle, DEFAULT_TEMPLATE},
+};
+
+const SETTINGS_FILE: &str = "settings.json";
+
+/// User-tunable behaviour, saved as `settings.json` in the app data dir.
+/// Every field has a default so older files keep loading as fields are added.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Settings {
+    /// Screenshots named in parallel
+    pub worker_count: usize,
+    /// Scan watched folders for unnamed screenshots at launch
+    pub catch_up_on_startup: bool,
+    /// Scan again when the watcher is resumed after a pause
+    pub catch_up_on_resume: bool,
+    /// Older screenshots are left alone by the scan; 0 means no limit
+    pub catch_up_max_age_hours: u64,
+    /// Skip or hold on to screenshots taken while paused
+    pub pause_mode: PauseMode,
+    /// Recurring windows during which the watcher pauses itself
+    pub quiet_hours: Vec<QuietHours>,
+    /// What to do when the suggested name is already taken
+    pub conflict_strategy: ConflictStrategy,
+    /// Final file name built from tokens, e.g. `{date:%Y-%m-%d}_{name}_{seq}`
+    pub name_template: String,
+    /// Casing applied to the AI name and other word tokens
+    pub name_case: CaseStyle,
+    /// Move renamed screenshots into a folder tree
+    pub organize: OrganizeSettings,
+    /// Which backend names screenshots and how to reach it
+    pub naming: NamingSettings,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            worker_count: 2,
+            catch_up_on_startup: true,
+            catch_up_on_resume: false,
+            catch_up_max_age_hours: 24,
+            pause_mode: PauseMode::Skip,
+            quiet_hours: Vec::new(),
+            conflict_strategy: ConflictStrategy::Numeric,
+            name_template: DEFAULT_TEMPLATE.to_string(),
+            name_case: CaseStyle::Original,
+            organize: OrganizeSettings::default(),
+            naming: NamingSettings::default(),
+        }
+    }
+}
+
+impl Settings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.worker_count == 0 || self.worker_count > 16 {
+            return Err("Worker count must be between 1 and 16".to_string());
+        }
+        for quiet_hours in &self.quiet_hours {
+            quiet_hours.validate()?;
+        }
+        validate_template(&self.name_template)?;
+        self.organize.validate()?;
+        self.naming.validate()?;
+        Ok(())
+    }
+}
+
+pub fn load_settings(app_handle: &AppHandle) -> Settings {
+    let settings: Settings = match data_file(app_handle, SETTINGS_FILE) {
+        Some(path) => load_json(&path),
+        None => Settings::default(),
+    };
+
+    if let Err(e) = settings.validate() {
+        eprintln!("❌ Saved settings are invalid, using defaults: {}", e);
+        return Settings::default();
+    }
+    settings
+}
+
+pub fn save_settings(app_handle: &AppHandle, settings: &Settings) {
+    let Some(path) = data_file(app_handle, SETTINGS_FILE) else {
+        return;
+    };
+    if let Err(e) = save_json(&path, settings) {
+        eprintln!("❌ Failed to save settings: {}", e);
+    }
+}
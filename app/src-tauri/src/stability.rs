@@ -0,0 +1,303 @@
+use std::{
+    fs,
+    io::{Read, Seek, SeekFrom},
+    path::Path,
+    time::{Duration, Instant, SystemTime},
+};
+
+use crate::{
+    image_format::{detect_file_format, ImageFormat},
+    queue::CancellationToken,
+};
+
+/// How long to wait for a screenshot tool to finish writing a file.
+#[derive(Debug, Clone, Copy)]
+pub struct StabilityConfig {
+    /// Size and mtime must stay unchanged for this long
+    pub quiet_period: Duration,
+    pub poll_interval: Duration,
+    /// Give up once the file has been changing (or missing) for this long
+    pub timeout: Duration,
+}
+
+impl Default for StabilityConfig {
+    fn default() -> Self {
+        StabilityConfig {
+            quiet_period: Duration::from_millis(500),
+            poll_interval: Duration::from_millis(100),
+            timeout: Duration::from_secs(15),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FileSnapshot {
+    len: u64,
+    modified: Option<SystemTime>,
+}
+
+fn snapshot(path: &Path) -> Option<FileSnapshot> {
+    let metadata = fs::metadata(path).ok()?;
+    if !metadata.is_file() {
+        return None;
+    }
+    Some(FileSnapshot {
+        len: metadata.len(),
+        modified: metadata.modified().ok(),
+    })
+}
+
+/// Blocks until the file has stopped changing for `quiet_period` and its
+/// container is complete, or returns false after `timeout` or once `cancel`
+/// fires.
+pub fn wait_until_stable(
+    path: &Path,
+    config: &StabilityConfig,
+    cancel: &CancellationToken,
+) -> bool {
+    let started = Instant::now();
+    let mut last: Option<FileSnapshot> = None;
+    let mut unchanged_since = Instant::now();
+
+    while started.elapsed() < config.timeout {
+        let current = snapshot(path);
+
+        if current != last {
+            last = current;
+            unchanged_since = Instant::now();
+        } else if let Some(snap) = current {
+            let quiet = unchanged_since.elapsed() >= config.quiet_period;
+            // An empty file is a placeholder the tool hasn't written to yet
+            if quiet && snap.len > 0 && is_container_complete(path) {
+                return true;
+            }
+        }
+
+        if cancel.sleep(config.poll_interval) {
+            return false;
+        }
+    }
+
+    sentry::capture_message(
+        &format!("❌ File never became stable: {}", path.display()),
+        sentry::Level::Warning,
+    );
+    false
+}
+
+/// Checks for the trailer every complete file of that format ends with.
+/// Formats without a cheap end marker are trusted once they stop changing.
+pub fn is_container_complete(path: &Path) -> bool {
+    let Some(format) = detect_file_format(path) else {
+        return true;
+    };
+
+    match format {
+        ImageFormat::Png => {
+            // Zero-length IEND chunk followed by its fixed CRC
+            const IEND: [u8; 12] = [
+                0x00, 0x00, 0x00, 0x00, b'I', b'E', b'N', b'D', 0xAE, 0x42, 0x60, 0x82,
+            ];
+            read_tail(path, IEND.len()).is_some_and(|tail| tail == IEND)
+        }
+        ImageFormat::Jpeg => read_tail(path, 64).is_some_and(|tail| {
+            // Some encoders pad past the EOI marker with zeros
+            let end = tail.iter().rposition(|&b| b != 0x00).map_or(0, |i| i + 1);
+            tail[..end].ends_with(&[0xFF, 0xD9])
+        }),
+        ImageFormat::Gif => read_tail(path, 1).is_some_and(|tail| tail == [0x3B]),
+        ImageFormat::Webp => {
+            // RIFF header stores the payload size after the 8-byte header
+            let Ok(mut file) = fs::File::open(path) else {
+                return false;
+            };
+            let mut header = [0u8; 8];
+            if file.read_exact(&mut header).is_err() {
+                return false;
+            }
+            let riff_len = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+            let file_len = file.metadata().map(|m| m.len()).unwrap_or(0);
+            file_len >= riff_len as u64 + 8
+        }
+        ImageFormat::Tiff | ImageFormat::Heic => true,
+    }
+}
+
+fn read_tail(path: &Path, len: usize) -> Option<Vec<u8>> {
+    let mut file = fs::File::open(path).ok()?;
+    let file_len = file.metadata().ok()?.len();
+    let len = len.min(file_len as usize);
+    file.seek(SeekFrom::End(-(len as i64))).ok()?;
+    let mut tail = vec![0u8; len];
+    file.read_exact(&mut tail).ok()?;
+    Some(tail)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{self, OpenOptions};
+    use std::io::Write;
+    use std::path::{Path, PathBuf};
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+    const PNG_IEND: [u8; 12] = [
+        0x00, 0x00, 0x00, 0x00, b'I', b'E', b'N', b'D', 0xAE, 0x42, 0x60, 0x82,
+    ];
+
+    fn test_config() -> StabilityConfig {
+        StabilityConfig {
+            quiet_period: Duration::from_millis(300),
+            poll_interval: Duration::from_millis(20),
+            timeout: Duration::from_secs(5),
+        }
+    }
+
+    /// Appends `chunks` to `path` one at a time with `delay` between them,
+    /// the way a screenshot tool streams a large capture to disk.
+    fn write_slowly(
+        path: PathBuf,
+        chunks: Vec<Vec<u8>>,
+        delay: Duration,
+    ) -> thread::JoinHandle<()> {
+        thread::spawn(move || {
+            for chunk in chunks {
+                let mut file = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&path)
+                    .unwrap();
+                file.write_all(&chunk).unwrap();
+                file.sync_all().unwrap();
+                thread::sleep(delay);
+            }
+        })
+    }
+
+    fn cleanup(path: &Path) {
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_waits_for_slow_png_writer() {
+        let path = PathBuf::from("tests/assets/Screenshot_slow_png.png");
+        fs::create_dir_all("tests/assets").unwrap();
+        cleanup(&path);
+
+        let chunks = vec![
+            PNG_SIGNATURE.to_vec(),
+            vec![0u8; 1024],
+            vec![1u8; 1024],
+            PNG_IEND.to_vec(),
+        ];
+        // Each gap is shorter than the quiet period, so only the final state counts
+        let writer = write_slowly(path.clone(), chunks, Duration::from_millis(150));
+
+        let started = Instant::now();
+        assert!(
+            wait_until_stable(&path, &test_config(), &CancellationToken::new()),
+            "PNG should become stable"
+        );
+        assert!(
+            started.elapsed() >= Duration::from_millis(450),
+            "Should not report stable while chunks are still arriving"
+        );
+        assert_eq!(fs::metadata(&path).unwrap().len(), 8 + 1024 + 1024 + 12);
+
+        writer.join().unwrap();
+        cleanup(&path);
+    }
+
+    #[test]
+    fn test_truncated_png_never_stable() {
+        let path = PathBuf::from("tests/assets/Screenshot_truncated.png");
+        fs::create_dir_all("tests/assets").unwrap();
+        cleanup(&path);
+
+        // Writer stalls before the IEND chunk, e.g. the tool crashed
+        let writer = write_slowly(
+            path.clone(),
+            vec![PNG_SIGNATURE.to_vec(), vec![0u8; 512]],
+            Duration::from_millis(50),
+        );
+        writer.join().unwrap();
+
+        let config = StabilityConfig {
+            timeout: Duration::from_secs(1),
+            ..test_config()
+        };
+        assert!(!is_container_complete(&path));
+        assert!(
+            !wait_until_stable(&path, &config, &CancellationToken::new()),
+            "Truncated PNG should time out"
+        );
+
+        cleanup(&path);
+    }
+
+    #[test]
+    fn test_waits_for_slow_jpeg_writer() {
+        let path = PathBuf::from("tests/assets/Screenshot_slow_jpeg.jpg");
+        fs::create_dir_all("tests/assets").unwrap();
+        cleanup(&path);
+
+        let chunks = vec![
+            vec![0xFF, 0xD8, 0xFF, 0xE0],
+            vec![0x42; 2048],
+            vec![0xFF, 0xD9],
+        ];
+        let writer = write_slowly(path.clone(), chunks, Duration::from_millis(150));
+
+        assert!(
+            wait_until_stable(&path, &test_config(), &CancellationToken::new()),
+            "JPEG should become stable"
+        );
+        assert!(is_container_complete(&path), "EOI marker should be present");
+
+        writer.join().unwrap();
+        cleanup(&path);
+    }
+
+    #[test]
+    fn test_file_created_late() {
+        let path = PathBuf::from("tests/assets/Screenshot_late.gif");
+        fs::create_dir_all("tests/assets").unwrap();
+        cleanup(&path);
+
+        // Nothing exists yet when we start waiting
+        let late_path = path.clone();
+        let writer = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(300));
+            fs::write(&late_path, b"GIF89a\x01\x00\x01\x00\x00\x00\x00;").unwrap();
+        });
+
+        assert!(
+            wait_until_stable(&path, &test_config(), &CancellationToken::new()),
+            "GIF should become stable"
+        );
+
+        writer.join().unwrap();
+        cleanup(&path);
+    }
+
+    #[test]
+    fn test_cancel_stops_waiting() {
+        // Never written, so only cancelling ends the wait before the timeout
+        let path = PathBuf::from("tests/assets/Screenshot_cancelled.png");
+        cleanup(&path);
+        let cancel = CancellationToken::new();
+        let canceller = cancel.clone();
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(100));
+            canceller.cancel();
+        });
+
+        let started = Instant::now();
+        assert!(!wait_until_stable(&path, &test_config(), &cancel));
+        assert!(started.elapsed() < test_config().timeout);
+
+        handle.join().unwrap();
+    }
+}
@@ -1,12 +1,35 @@
-use std::{path::PathBuf, sync::{Arc, Mutex}};
-
-use crate::user::UserDevice;
-
+use std::sync::{Arc, Mutex};
 
+use crate::{
+    catch_up::CatchUpReport,
+    folders::WatchedFolder,
+    history::RenameHistory,
+    inbox::ReviewInbox,
+    ledger::ProcessedLedger,
+    offline_queue::OfflineQueue,
+    pause::{DeferredScreenshots, PauseState},
+    queue::JobQueue,
+    rules::Rule,
+    settings::Settings,
+    template::SequenceCounter,
+    user::UserDevice,
+    watch::WatcherControl,
+};
 
 #[derive(Clone)]
 pub struct AppState {
-    pub paused: Arc<Mutex<bool>>,
+    pub paused: Arc<Mutex<PauseState>>,
     pub user_device: UserDevice,
-    pub selected_path: Arc<Mutex<String>>,
+    pub watched_folders: Arc<Mutex<Vec<WatchedFolder>>>,
+    pub watcher: WatcherControl,
+    pub queue: JobQueue,
+    pub settings: Arc<Mutex<Settings>>,
+    pub catch_up: Arc<Mutex<Option<CatchUpReport>>>,
+    pub deferred: DeferredScreenshots,
+    pub ledger: ProcessedLedger,
+    pub sequence: SequenceCounter,
+    pub rules: Arc<Mutex<Vec<Rule>>>,
+    pub inbox: ReviewInbox,
+    pub history: RenameHistory,
+    pub offline: OfflineQueue,
 }
@@ -0,0 +1,57 @@
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+use tauri::{AppHandle, Manager};
+
+/// Path of a file inside the app data dir, creating the dir if needed.
+pub fn data_file(app_handle: &AppHandle, name: &str) -> Option<PathBuf> {
+    let dir = match app_handle.path().app_data_dir() {
+        Ok(dir) => dir,
+        Err(e) => {
+            eprintln!("❌ Failed to resolve app data directory: {}", e);
+            return None;
+        }
+    };
+
+    if let Err(e) = fs::create_dir_all(&dir) {
+        eprintln!(
+            "❌ Failed to create app data directory {}: {}",
+            dir.display(),
+            e
+        );
+        return None;
+    }
+
+    Some(dir.join(name))
+}
+
+/// Reads a JSON file, falling back to `T::default()` when it is missing or unreadable.
+pub fn load_json<T: DeserializeOwned + Default>(path: &Path) -> T {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return T::default(),
+        Err(e) => {
+            eprintln!("❌ Failed to read {}: {}", path.display(), e);
+            return T::default();
+        }
+    };
+
+    match serde_json::from_str(&contents) {
+        Ok(value) => value,
+        Err(e) => {
+            eprintln!("❌ Failed to parse {}: {}", path.display(), e);
+            T::default()
+        }
+    }
+}
+
+/// Writes JSON to a temp file next to `path` and renames it over, so a crash
+/// mid-write never leaves a truncated file behind.
+pub fn save_json<T: Serialize>(path: &Path, value: &T) -> io::Result<()> {
+    let json = serde_json::to_string_pretty(value)?;
+    let tmp_path = path.with_extension("json.tmp");
+    fs::write(&tmp_path, json)?;
+    fs::rename(&tmp_path, path)
+}
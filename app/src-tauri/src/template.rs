@@ -0,0 +1,530 @@
+use chrono::{
+    format::{Item, StrftimeItems},
+    DateTime, Local, NaiveDate,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::Write,
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+use tauri::AppHandle;
+
+use crate::{
+    folders::WatchedFolder,
+    store::{data_file, load_json, save_json},
+};
+
+/// Keeps the old behaviour: the file is named exactly what the AI suggested.
+pub const DEFAULT_TEMPLATE: &str = "{name}";
+
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+
+const SEQUENCE_FILE: &str = "sequence.json";
+
+/// How the word-like tokens (`{name}`, `{original}`, `{folder}`) are cased.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CaseStyle {
+    /// Leave the text as it is
+    #[default]
+    Original,
+    /// `login-page-error`
+    Kebab,
+    /// `login_page_error`
+    Snake,
+    /// `Login Page Error`
+    Title,
+    /// `loginPageError`
+    Camel,
+}
+
+impl CaseStyle {
+    pub fn apply(self, text: &str) -> String {
+        if self == CaseStyle::Original {
+            return text.to_string();
+        }
+
+        let words = split_words(text);
+        match self {
+            CaseStyle::Original => unreachable!(),
+            CaseStyle::Kebab => join_lowercase(&words, "-"),
+            CaseStyle::Snake => join_lowercase(&words, "_"),
+            CaseStyle::Title => words
+                .iter()
+                .map(|w| capitalize(w))
+                .collect::<Vec<_>>()
+                .join(" "),
+            CaseStyle::Camel => words
+                .iter()
+                .enumerate()
+                .map(|(i, w)| {
+                    if i == 0 {
+                        w.to_lowercase()
+                    } else {
+                        capitalize(w)
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Splits on anything that isn't a letter or digit, and on camelCase humps.
+fn split_words(text: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut prev_lower = false;
+
+    for c in text.chars() {
+        if !c.is_alphanumeric() {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            prev_lower = false;
+            continue;
+        }
+        if c.is_uppercase() && prev_lower {
+            words.push(std::mem::take(&mut current));
+        }
+        prev_lower = c.is_lowercase() || c.is_ascii_digit();
+        current.push(c);
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+    words
+}
+
+fn join_lowercase(words: &[String], separator: &str) -> String {
+    words
+        .iter()
+        .map(|w| w.to_lowercase())
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first
+            .to_uppercase()
+            .chain(chars.flat_map(char::to_lowercase))
+            .collect(),
+        None => String::new(),
+    }
+}
+
+/// Everything a template can refer to for one screenshot.
+#[derive(Debug, Clone)]
+pub struct TemplateContext {
+    /// The AI-generated name
+    pub name: String,
+    /// File name before renaming, without extension
+    pub original: String,
+    /// Name of the watched folder the screenshot landed in
+    pub folder: String,
+    pub captured_at: DateTime<Local>,
+    /// Per-day counter, starting at 1
+    pub seq: u32,
+    pub host: String,
+}
+
+impl TemplateContext {
+    pub fn for_screenshot(
+        path: &Path,
+        name: &str,
+        watched_folders: &[WatchedFolder],
+        seq: u32,
+    ) -> Self {
+        TemplateContext {
+            name: name.to_string(),
+            original: path
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            folder: folder_label(path, watched_folders),
+            captured_at: capture_time(path),
+            seq,
+            host: host_name(),
+        }
+    }
+
+    /// Made-up screenshot for previewing a template in settings.
+    pub fn sample(watched_folders: &[WatchedFolder], seq: u32) -> Self {
+        let folder = watched_folders
+            .first()
+            .and_then(|f| Path::new(&f.path).file_name())
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| "Desktop".to_string());
+        let captured_at = Local::now();
+        TemplateContext {
+            name: "login page error".to_string(),
+            original: format!("Screenshot {}", captured_at.format("%Y-%m-%d at %H.%M.%S")),
+            folder,
+            captured_at,
+            seq,
+            host: host_name(),
+        }
+    }
+}
+
+/// Name of the watched folder containing `path`, or of its parent directory
+/// when it isn't in one (e.g. a folder that was removed since).
+fn folder_label(path: &Path, watched_folders: &[WatchedFolder]) -> String {
+    let folder = watched_folders
+        .iter()
+        .map(|f| Path::new(&f.path))
+        .filter(|f| path.starts_with(f))
+        .max_by_key(|f| f.components().count())
+        .or_else(|| path.parent());
+    folder
+        .and_then(|f| f.file_name())
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// When the screenshot was taken: creation time where the OS records it,
+/// otherwise the last modification.
+pub fn capture_time(path: &Path) -> DateTime<Local> {
+    fs::metadata(path)
+        .and_then(|m| m.created().or_else(|_| m.modified()))
+        .map(DateTime::<Local>::from)
+        .unwrap_or_else(|_| Local::now())
+}
+
+pub fn host_name() -> String {
+    gethostname::gethostname().to_string_lossy().to_string()
+}
+
+enum Segment<'a> {
+    Literal(String),
+    Token { name: &'a str, arg: Option<&'a str> },
+}
+
+fn parse(template: &str) -> Result<Vec<Segment<'_>>, String> {
+    let mut segments = Vec::new();
+    let mut literal = String::new();
+    let mut rest = template;
+
+    while let Some(c) = rest.chars().next() {
+        match c {
+            '{' if rest.starts_with("{{") => {
+                literal.push('{');
+                rest = &rest[2..];
+            }
+            '}' if rest.starts_with("}}") => {
+                literal.push('}');
+                rest = &rest[2..];
+            }
+            '{' => {
+                let end = rest
+                    .find('}')
+                    .ok_or_else(|| format!("Unclosed '{{' in template '{}'", template))?;
+                let inner = &rest[1..end];
+                let (name, arg) = match inner.split_once(':') {
+                    Some((name, arg)) => (name, Some(arg)),
+                    None => (inner, None),
+                };
+                if !literal.is_empty() {
+                    segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                }
+                segments.push(Segment::Token {
+                    name: name.trim(),
+                    arg,
+                });
+                rest = &rest[end + 1..];
+            }
+            '}' => return Err(format!("Unmatched '}}' in template '{}'", template)),
+            c => {
+                literal.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+    if !literal.is_empty() {
+        segments.push(Segment::Literal(literal));
+    }
+    Ok(segments)
+}
+
+/// Checks that every token exists and its argument makes sense.
+pub fn validate_template(template: &str) -> Result<(), String> {
+    if template.trim().is_empty() {
+        return Err("Name template can't be empty".to_string());
+    }
+    for segment in parse(template)? {
+        let Segment::Token { name, arg } = segment else {
+            continue;
+        };
+        match (name, arg) {
+            ("name" | "original" | "folder" | "host", None) => {}
+            ("date", None) => {}
+            ("date", Some(format)) => {
+                if StrftimeItems::new(format).any(|item| item == Item::Error) {
+                    return Err(format!("Invalid date format '{}'", format));
+                }
+            }
+            ("seq", None) => {}
+            ("seq", Some(width)) => {
+                if !matches!(width.parse::<usize>(), Ok(1..=9)) {
+                    return Err(format!("Counter width must be 1-9, got '{}'", width));
+                }
+            }
+            ("name" | "original" | "folder" | "host", Some(_)) => {
+                return Err(format!("{{{}}} doesn't take an argument", name));
+            }
+            _ => return Err(format!("Unknown template token {{{}}}", name)),
+        }
+    }
+    Ok(())
+}
+
+/// Whether rendering `template` consumes a value from the per-day counter.
+pub fn uses_sequence(template: &str) -> bool {
+    parse(template).is_ok_and(|segments| {
+        segments
+            .iter()
+            .any(|s| matches!(s, Segment::Token { name: "seq", .. }))
+    })
+}
+
+/// Fills in `template`, e.g. `{date:%Y-%m-%d}_{name}_{seq}`.
+pub fn render_template(
+    template: &str,
+    case: CaseStyle,
+    context: &TemplateContext,
+) -> Result<String, String> {
+    validate_template(template)?;
+
+    let mut out = String::new();
+    for segment in parse(template)? {
+        match segment {
+            Segment::Literal(text) => out.push_str(&text),
+            Segment::Token { name, arg } => match name {
+                "name" => out.push_str(&case.apply(&context.name)),
+                "original" => out.push_str(&case.apply(&context.original)),
+                "folder" => out.push_str(&case.apply(&context.folder)),
+                "host" => out.push_str(&context.host),
+                "date" => {
+                    let items = StrftimeItems::new(arg.unwrap_or(DEFAULT_DATE_FORMAT));
+                    write!(out, "{}", context.captured_at.format_with_items(items))
+                        .map_err(|_| "Failed to format capture date".to_string())?;
+                }
+                "seq" => {
+                    let width = arg.and_then(|w| w.parse().ok()).unwrap_or(1);
+                    write!(out, "{:0width$}", context.seq, width = width)
+                        .map_err(|_| "Failed to format counter".to_string())?;
+                }
+                _ => unreachable!("validated above"),
+            },
+        }
+    }
+    Ok(out)
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DailySequence {
+    date: Option<NaiveDate>,
+    last: u32,
+}
+
+/// The `{seq}` counter, saved as `sequence.json` so it keeps counting across
+/// restarts and resets every day.
+#[derive(Clone)]
+pub struct SequenceCounter {
+    inner: Arc<Mutex<DailySequence>>,
+    file: Option<PathBuf>,
+}
+
+impl SequenceCounter {
+    pub fn load(app_handle: &AppHandle) -> Self {
+        Self::open(data_file(app_handle, SEQUENCE_FILE))
+    }
+
+    /// Reads the counter from `file`; `None` keeps it in memory only.
+    pub fn open(file: Option<PathBuf>) -> Self {
+        let sequence = match &file {
+            Some(path) => load_json(path),
+            None => DailySequence::default(),
+        };
+        SequenceCounter {
+            inner: Arc::new(Mutex::new(sequence)),
+            file,
+        }
+    }
+
+    /// The value `next` would hand out, without using it up.
+    pub fn peek(&self, today: NaiveDate) -> u32 {
+        let sequence = self.inner.lock().unwrap();
+        match sequence.date {
+            Some(date) if date == today => sequence.last + 1,
+            _ => 1,
+        }
+    }
+
+    pub fn next(&self, today: NaiveDate) -> u32 {
+        let mut sequence = self.inner.lock().unwrap();
+        if sequence.date != Some(today) {
+            sequence.date = Some(today);
+            sequence.last = 0;
+        }
+        sequence.last += 1;
+
+        if let Some(file) = &self.file {
+            if let Err(e) = save_json(file, &*sequence) {
+                eprintln!("❌ Failed to save sequence counter: {}", e);
+            }
+        }
+        sequence.last
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Local, NaiveDate, TimeZone};
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn context() -> TemplateContext {
+        TemplateContext {
+            name: "login page error".to_string(),
+            original: "Screenshot 2025-06-12 at 10.22.33".to_string(),
+            folder: "Desktop".to_string(),
+            captured_at: Local.with_ymd_and_hms(2025, 6, 12, 10, 22, 33).unwrap(),
+            seq: 7,
+            host: "studio-mac".to_string(),
+        }
+    }
+
+    fn render(template: &str) -> String {
+        render_template(template, CaseStyle::Original, &context()).unwrap()
+    }
+
+    #[test]
+    fn test_default_template_is_ai_name() {
+        assert_eq!(render("{name}"), "login page error");
+    }
+
+    #[test]
+    fn test_tokens() {
+        assert_eq!(render("{original}"), "Screenshot 2025-06-12 at 10.22.33");
+        assert_eq!(render("{folder}"), "Desktop");
+        assert_eq!(render("{host}"), "studio-mac");
+        assert_eq!(render("{seq}"), "7");
+        assert_eq!(render("{seq:3}"), "007");
+        assert_eq!(render("{date}"), "2025-06-12");
+        assert_eq!(render("{date:%Y%m%d-%H%M}"), "20250612-1022");
+    }
+
+    #[test]
+    fn test_combined_template() {
+        assert_eq!(
+            render("{date:%Y-%m-%d}_{name}_{seq}"),
+            "2025-06-12_login page error_7"
+        );
+        assert_eq!(
+            render("{folder} - {name} ({host})"),
+            "Desktop - login page error (studio-mac)"
+        );
+    }
+
+    #[test]
+    fn test_escaped_braces() {
+        assert_eq!(render("{{{name}}}"), "{login page error}");
+    }
+
+    #[test]
+    fn test_case_styles() {
+        let cases = [
+            (CaseStyle::Original, "login page error"),
+            (CaseStyle::Kebab, "login-page-error"),
+            (CaseStyle::Snake, "login_page_error"),
+            (CaseStyle::Title, "Login Page Error"),
+            (CaseStyle::Camel, "loginPageError"),
+        ];
+        for (case, expected) in cases {
+            assert_eq!(
+                render_template("{name}", case, &context()).unwrap(),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_case_style_word_splitting() {
+        assert_eq!(
+            CaseStyle::Snake.apply("login-page_error.v2"),
+            "login_page_error_v2"
+        );
+        assert_eq!(CaseStyle::Kebab.apply("loginPageError"), "login-page-error");
+        assert_eq!(CaseStyle::Title.apply("LOGIN page"), "Login Page");
+        assert_eq!(CaseStyle::Camel.apply("Login-Page"), "loginPage");
+        assert_eq!(CaseStyle::Kebab.apply("  --  "), "");
+    }
+
+    #[test]
+    fn test_case_style_leaves_other_tokens_alone() {
+        let name = render_template("{date}_{name}_{host}", CaseStyle::Snake, &context()).unwrap();
+        assert_eq!(name, "2025-06-12_login_page_error_studio-mac");
+    }
+
+    #[test]
+    fn test_invalid_templates() {
+        assert!(validate_template("").is_err());
+        assert!(validate_template("{unknown}").is_err());
+        assert!(validate_template("{name").is_err());
+        assert!(validate_template("name}").is_err());
+        assert!(validate_template("{name:upper}").is_err());
+        assert!(validate_template("{seq:0}").is_err());
+        assert!(validate_template("{seq:abc}").is_err());
+        assert!(validate_template("{date:%Q}").is_err());
+
+        assert!(validate_template("{date:%Y-%m-%d}_{name}_{seq:2}").is_ok());
+        assert!(validate_template("static name").is_ok());
+    }
+
+    #[test]
+    fn test_uses_sequence() {
+        assert!(uses_sequence("{name}_{seq}"));
+        assert!(uses_sequence("{seq:3}"));
+        assert!(!uses_sequence("{name}"));
+        assert!(!uses_sequence("{{seq}}"));
+    }
+
+    #[test]
+    fn test_sequence_resets_each_day() {
+        let counter = SequenceCounter::open(None);
+        let monday = NaiveDate::from_ymd_opt(2025, 6, 9).unwrap();
+        let tuesday = NaiveDate::from_ymd_opt(2025, 6, 10).unwrap();
+
+        assert_eq!(counter.peek(monday), 1);
+        assert_eq!(counter.next(monday), 1);
+        assert_eq!(counter.next(monday), 2);
+        assert_eq!(counter.peek(monday), 3);
+
+        assert_eq!(counter.peek(tuesday), 1);
+        assert_eq!(counter.next(tuesday), 1);
+    }
+
+    #[test]
+    fn test_sequence_survives_reopen() {
+        let file = PathBuf::from("tests/assets/sequence_reopen.json");
+        fs::create_dir_all("tests/assets").unwrap();
+        let _ = fs::remove_file(&file);
+        let today = NaiveDate::from_ymd_opt(2025, 6, 12).unwrap();
+
+        let counter = SequenceCounter::open(Some(file.clone()));
+        counter.next(today);
+        counter.next(today);
+        drop(counter);
+
+        let reopened = SequenceCounter::open(Some(file.clone()));
+        assert_eq!(reopened.next(today), 3);
+
+        fs::remove_file(&file).unwrap();
+    }
+}
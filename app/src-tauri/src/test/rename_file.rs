@@ -11,10 +11,11 @@ fn test_rename_file() {
     let new_name = "renamed_test_file".to_string();
 
     // Call the function
-    rename_file(&original_path, &new_name);
+    let result = rename_file(&original_path, &new_name, ConflictStrategy::Numeric);
 
     // Check new file exists
     let renamed_path = PathBuf::from(format!("{}.png", new_name));
+    assert_eq!(result, Ok(Some(renamed_path.clone())));
     assert!(renamed_path.exists(), "Renamed file should exist");
 
     // Cleanup
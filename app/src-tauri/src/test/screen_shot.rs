@@ -8,6 +8,7 @@ fn test_is_new_screenshot() {
     let nonexistent = PathBuf::from("/some/clearly/fake/Screenshot_does_not_exist.png");
     let wrong_ext = PathBuf::from("tests/assets/screenshot.txt");
     let wrong_name = PathBuf::from("tests/assets/image_01.png");
+    let matcher = ScreenshotMatcher::default();
 
 
 
@@ -16,10 +17,10 @@ fn test_is_new_screenshot() {
     std::fs::create_dir_all("tests/assets").unwrap();
     std::fs::write(&valid_existing, b"test").unwrap();
 
-    assert!(is_new_screenshot(&valid_existing), "Should detect real screenshot");
-    assert!(!is_new_screenshot(&nonexistent), "Should skip nonexistent file");
-    assert!(!is_new_screenshot(&wrong_ext), "Wrong extension should return false");
-    assert!(!is_new_screenshot(&wrong_name), "Wrong name should return false");
+    assert!(is_new_screenshot(&valid_existing, &matcher), "Should detect real screenshot");
+    assert!(!is_new_screenshot(&nonexistent, &matcher), "Should skip nonexistent file");
+    assert!(!is_new_screenshot(&wrong_ext, &matcher), "Wrong extension should return false");
+    assert!(!is_new_screenshot(&wrong_name, &matcher), "Wrong name should return false");
 
     // Clean up
     std::fs::remove_file(&valid_existing).unwrap();
@@ -1,13 +1,13 @@
 use std::env;
-// use reqwest::Client;
-use reqwest::blocking::Client;  // sync
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
 use serde_with::serde_as;
 use dotenvy_macro::dotenv;
 
+use crate::http::client;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
 pub enum Tier {
     #[serde(rename = "free")]
     Free,
@@ -93,12 +93,11 @@ pub fn register() -> Option<UserDevice> {
 
     dotenvy::dotenv().ok();
 
-    let client = Client::new();
     let register_url = dotenv!("REGISTER_URL");
 
 
 // Send a POST request to the register_url with our JSON payload
-let result = client.post(register_url).json(&payload).send();
+let result = client().post(register_url).json(&payload).send();
 
 let response = match result{ 
     Ok(res) => {
@@ -1,24 +1,33 @@
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
+use chrono::Utc;
 use std::{
     fs,
-    path::Path,
-    sync::{Arc, Mutex},
+    path::{Path, PathBuf},
 };
-use tauri::{AppHandle, Emitter, Manager};
+use tauri::{AppHandle, Emitter};
 // use tauri::tray::{TrayIconEvent, MouseButton, MouseButtonState};
 
 #[cfg(target_os = "macos")]
 use dotenvy::dotenv;
-use notify::{recommended_watcher, Event, RecursiveMode, Result, Watcher};
-use std::collections::HashMap;
+use notify::{recommended_watcher, Event, RecommendedWatcher, RecursiveMode, Result, Watcher};
+use std::collections::{HashMap, HashSet};
 use std::sync::mpsc;
-use std::thread;
 use std::time::Duration;
 use std::time::Instant;
 
-use crate::{generate_name::generate_screenshot_name, user::UserDevice};
+use crate::{
+    catch_up::{find_unnamed_screenshots, CatchUpReport, ScanReason},
+    detect::{load_detection_rules, ScreenshotMatcher},
+    folders::{enabled_paths, WatchedFolder},
+    pause::{
+        current_pause_status, defer_screenshot, emit_pause_status, flush_deferred, PauseMode,
+        PauseStatus,
+    },
+    rename::{rename_no_clobber, ConflictStrategy},
+    state::AppState,
+};
 
-pub fn is_new_screenshot(path: &Path) -> bool {
+pub fn is_new_screenshot(path: &Path, matcher: &ScreenshotMatcher) -> bool {
     if path.is_dir() {
         return false; // Ignore directories
     }
@@ -30,10 +39,7 @@ pub fn is_new_screenshot(path: &Path) -> bool {
         return false; // Ignore paths that cannot be checked
     }
 
-    path.extension().map_or(false, |ext| ext == "png")
-        && path.file_name().map_or(false, |name| {
-            name.to_string_lossy().to_lowercase().contains("screenshot")
-        })
+    matcher.matches(path)
 }
 
 pub fn get_file_extension(file_path: &Path) -> &str {
@@ -44,116 +50,193 @@ pub fn get_file_extension(file_path: &Path) -> &str {
     }
 }
 
-pub fn rename_file(file_to_edit: &Path, new_file_name: &String) {
+/// Renames the screenshot next to where it is, never replacing another file.
+/// `strategy` decides what happens when the name is already taken.
+pub fn rename_file(
+    file_to_edit: &Path,
+    new_file_name: &String,
+    strategy: ConflictStrategy,
+) -> std::result::Result<Option<PathBuf>, String> {
+    let parent_dir = file_to_edit
+        .parent()
+        .ok_or("Can't determine parent directory")?;
+
+    move_file(file_to_edit, parent_dir, new_file_name, strategy)
+}
+
+/// Renames the screenshot into `target_dir`, creating it if needed. Returns the
+/// final path, or `None` if the name was taken and `strategy` is `Skip`.
+pub fn move_file(
+    file_to_edit: &Path,
+    target_dir: &Path,
+    new_file_name: &String,
+    strategy: ConflictStrategy,
+) -> std::result::Result<Option<PathBuf>, String> {
     println!("Renaming file: {}", file_to_edit.display());
 
-    let parent_dir = match file_to_edit.parent() {
-        Some(val) => val,
-        None => {
-            eprintln!("Can't determine parent directory");
-            return;
-        }
-    };
+    fs::create_dir_all(target_dir)
+        .map_err(|e| format!("Error creating {}: {}", target_dir.display(), e))?;
 
     let file_ext = get_file_extension(file_to_edit);
 
-    let mut new_file_path = parent_dir.join(&new_file_name); // giving this a reference so i can use it below as well
+    let res = rename_no_clobber(file_to_edit, target_dir, new_file_name, file_ext, strategy);
 
-    new_file_path.set_extension(file_ext);
+    match res {
+        Ok(Some(new_file_path)) => {
+            println!("Successfully renamed file to '{}'", new_file_path.display());
+            Ok(Some(new_file_path))
+        }
+        Ok(None) => {
+            println!(
+                "⏭️ '{}' already exists, leaving screenshot as is",
+                new_file_name
+            );
+            Ok(None)
+        }
+        Err(e) => Err(format!("Error renaming file: {}", e)),
+    }
+}
 
-    println!("paths {}", new_file_path.display());
-    let res = fs::rename(file_to_edit, new_file_path);
+/// Live changes to the watcher's configuration, sent from Tauri commands.
+pub enum WatcherCommand {
+    Watch(PathBuf),
+    Unwatch(PathBuf),
+    SetRules(ScreenshotMatcher),
+    /// Wake the loop so a pause or resume from the UI applies right away
+    PauseChanged,
+}
 
-    match res {
-        Ok(_) => println!("Successfully renamed file to '{}'", new_file_name),
-        Err(e) => eprintln!("Error renaming file: {}", e),
+/// Filesystem events and control commands share one channel so the loop can
+/// block on a single `recv` and react to either immediately.
+pub enum WatcherMessage {
+    Fs(Result<Event>),
+    Control(WatcherCommand),
+}
+
+impl WatcherCommand {
+    /// Starts or stops watching `folder` to match whether it is enabled.
+    pub fn for_folder(folder: &WatchedFolder) -> Self {
+        let dir = PathBuf::from(&folder.path);
+        if folder.enabled {
+            WatcherCommand::Watch(dir)
+        } else {
+            WatcherCommand::Unwatch(dir)
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct WatcherControl {
+    tx: mpsc::Sender<WatcherMessage>,
+}
+
+impl WatcherControl {
+    pub fn send(&self, command: WatcherCommand) {
+        if let Err(e) = self.tx.send(WatcherMessage::Control(command)) {
+            eprintln!("❌ Watcher thread is not running: {}", e);
+        }
     }
 }
 
+pub fn watcher_channel() -> (WatcherControl, mpsc::Receiver<WatcherMessage>) {
+    let (tx, rx) = mpsc::channel::<WatcherMessage>();
+    (WatcherControl { tx }, rx)
+}
+
 // pub fn watch_screenshots(paused_state: Arc<Mutex<bool>>) -> notify::Result<()> {
 pub fn watch_screenshots(
-    paused_state: Arc<Mutex<bool>>,
     app_handle: AppHandle,
-    user_device: UserDevice,
-    directory_to_watch: Arc<Mutex<String>>,
+    state: AppState,
+    rx: mpsc::Receiver<WatcherMessage>,
 ) -> notify::Result<()> {
     sentry::capture_message("In watch screenshots", sentry::Level::Info);
     dotenv().ok();
 
-    let (tx, rx) = mpsc::channel::<Result<Event>>();
-    let mut watcher = recommended_watcher(tx)?;
+    let fs_tx = state.watcher.tx.clone();
+    let mut watcher = recommended_watcher(move |res: Result<Event>| {
+        let _ = fs_tx.send(WatcherMessage::Fs(res));
+    })?;
 
-    // let screenshot_dir = std::env::var("HOME").unwrap() + "/Desktop";
+    // Directories that currently have a notify watch on them
+    let mut active_dirs: HashSet<PathBuf> = HashSet::new();
+    let initial_dirs = enabled_paths(&state.watched_folders.lock().unwrap());
+    for dir in initial_dirs {
+        add_watch(&mut watcher, &mut active_dirs, dir);
+    }
 
-    println!(
-        "📸 Watching screenshots in {}",
-        directory_to_watch.lock().expect("uh oh").clone()
-    );
+    sentry::capture_message("Watching screenshot folders", sentry::Level::Info);
 
-    // let screenshot_dir = {
-    //     let guard = directory_to_watch.lock().unwrap();
-    //     let path = guard.clone();
-
-    //     // Handle case where no directory was selected
-    //     if path.is_empty() {
-    //         return Err(notify::Error::generic("No directory selected for watching"));
-    //     }
-
-    //     // Validate that the path exists
-    //     if !std::path::Path::new(&path).exists() {
-    //         panic!("Directory does not exist: {}", path);
-    //         // return Err(notify::Error::generic(&format!(
-    //         //     "Directory does not exist: {}",
-    //         //     path
-    //         // )));
-    //     }
-
-    //     path
-    // };
-    // let guard = directory_to_watch.lock().unwrap();
-    // *guard
-    // };
-    let screenshot_dir:String = match app_handle.path().desktop_dir() {
-        Ok(path) => match path.to_str() {
-            Some(s) => s.to_string(),
-            None => {
-                eprintln!("❌ Failed to convert desktop path to UTF-8 string");
-                return Ok(());
-            }
-        },
+    let mut matcher = match load_detection_rules(&app_handle).compile() {
+        Ok(matcher) => matcher,
         Err(e) => {
-            eprintln!("❌ Failed to get desktop directory: {}", e);
-            panic!("Failed to get desktop directory");
+            eprintln!(
+                "❌ Saved detection rules are invalid, using defaults: {}",
+                e
+            );
+            ScreenshotMatcher::default()
         }
     };
 
-    watcher.watch(Path::new(&screenshot_dir), RecursiveMode::NonRecursive)?;
+    // Known before the catch-up, which must not queue anything while paused
+    let mut pause_status = current_pause_status(&state);
 
-    println!("📸 Watching screenshots in: {}", screenshot_dir);
-    sentry::capture_message("Watching screenshots in desktop", sentry::Level::Info);
+    // Deferred before the app last quit
+    if !pause_status.paused {
+        flush_deferred(&app_handle, &state);
+    }
+
+    // Pick up screenshots taken while the app was closed
+    if state.settings.lock().unwrap().catch_up_on_startup {
+        catch_up(
+            &app_handle,
+            &state,
+            &active_dirs,
+            &matcher,
+            ScanReason::Startup,
+            pause_status.paused,
+        );
+    }
 
     let mut recently_handled: HashMap<String, Instant> = HashMap::new();
     let cooldown = Duration::from_secs(5);
     // let state = app.state::<AppState>();
     sentry::capture_message("Entering loop", sentry::Level::Info);
     loop {
-        let is_paused = {
-            let guard = paused_state.lock().unwrap();
-            *guard
-        };
-
-        if is_paused {
-            // Draining the event queue to avoid blocking
-            // This will clear any pending events while paused
-            while rx.try_recv().is_ok() {}
-            thread::sleep(Duration::from_millis(100));
-            continue; // skip processing while paused
+        let message = rx.recv_timeout(Duration::from_secs(1));
+
+        // Timers and quiet hours flip the pause state without any event
+        let status = current_pause_status(&state);
+        if status != pause_status {
+            on_pause_changed(
+                &app_handle,
+                &state,
+                &active_dirs,
+                &matcher,
+                &pause_status,
+                &status,
+            );
+            pause_status = status;
         }
 
         let now = Instant::now();
 
-        match rx.recv_timeout(Duration::from_secs(1)) {
-            Ok(Ok(event)) => {
+        match message {
+            Ok(WatcherMessage::Control(command)) => match command {
+                WatcherCommand::Watch(dir) => add_watch(&mut watcher, &mut active_dirs, dir),
+                WatcherCommand::Unwatch(dir) => remove_watch(&mut watcher, &mut active_dirs, &dir),
+                WatcherCommand::SetRules(new_matcher) => {
+                    println!("🔎 Detection rules updated");
+                    matcher = new_matcher;
+                }
+                WatcherCommand::PauseChanged => {} // Already applied above
+            },
+            Ok(WatcherMessage::Fs(Ok(event))) => {
+                let is_paused = pause_status.paused;
+                let pause_mode = state.settings.lock().unwrap().pause_mode;
+                if is_paused && pause_mode == PauseMode::Skip {
+                    continue; // skip processing while paused
+                }
+
                 if event.paths.is_empty() {
                     continue; // No paths in the event
                 }
@@ -163,7 +246,7 @@ pub fn watch_screenshots(
                 }
 
                 for path in event.paths {
-                    if !is_new_screenshot(&path) {
+                    if !is_new_screenshot(&path, &matcher) {
                         continue;
                     }
 
@@ -179,26 +262,177 @@ pub fn watch_screenshots(
                     // Record the file as handled
                     recently_handled.insert(path_str.clone(), now);
 
-                    println!("🖼️ Processing new screenshot: {}", path.display());
-
-                    let name = generate_screenshot_name(&path, &user_device, &app_handle);
-                    println!("📁 Suggested name: {}", name);
-
-                    rename_file(&path, &name);
+                    // Same content at the same path was named before, e.g. a
+                    // touch after a restart
+                    if state.ledger.is_processed(&path) {
+                        println!("⏭️ Already named, skipping: {}", path.display());
+                        continue;
+                    }
 
-                    if let Err(e) = app_handle.emit_to("main", "screenshot-renamed", name.clone()) {
-                        eprintln!("❌ Failed to emit event: {:?}", e);
+                    if is_paused {
+                        defer_screenshot(&app_handle, &state, path);
+                        continue;
                     }
 
-                    // on_rename(&name);
-                    // notify_user(&name, &app_handle);
+                    // Naming happens on the worker pool so a slow request
+                    // never holds up event handling
+                    if let Some(job_id) = state.queue.enqueue(path.clone()) {
+                        println!("📥 Queued screenshot #{}: {}", job_id, path.display());
+                    }
                 }
             }
-            Ok(Err(e)) => println!("❌ Watch error: {:?}", e),
-            Err(_) => {} // Timeout — no new events
+            Ok(WatcherMessage::Fs(Err(e))) => println!("❌ Watch error: {:?}", e),
+            Err(mpsc::RecvTimeoutError::Timeout) => {} // No new events
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                eprintln!("❌ Watcher channel closed, stopping watcher");
+                return Ok(());
+            }
         }
 
         // Prune old entries
         recently_handled.retain(|_, &mut t| now.duration_since(t) < Duration::from_secs(30));
     }
 }
+
+/// Reports the new pause state and, on resume, catches up on whatever
+/// happened while paused.
+fn on_pause_changed(
+    app_handle: &AppHandle,
+    state: &AppState,
+    active_dirs: &HashSet<PathBuf>,
+    matcher: &ScreenshotMatcher,
+    previous: &PauseStatus,
+    current: &PauseStatus,
+) {
+    println!(
+        "⏯️ Watcher paused: {} ({:?}, until {:?})",
+        current.paused, current.reason, current.until
+    );
+    emit_pause_status(app_handle, current);
+
+    if previous.paused && !current.paused {
+        flush_deferred(app_handle, state);
+        if state.settings.lock().unwrap().catch_up_on_resume {
+            catch_up(
+                app_handle,
+                state,
+                active_dirs,
+                matcher,
+                ScanReason::Resume,
+                current.paused,
+            );
+        }
+    }
+}
+
+/// Queues every unnamed screenshot in the watched folders and reports the
+/// backlog to the UI. While `paused`, they are deferred or left alone like
+/// any other screenshot, depending on the pause mode.
+fn catch_up(
+    app_handle: &AppHandle,
+    state: &AppState,
+    active_dirs: &HashSet<PathBuf>,
+    matcher: &ScreenshotMatcher,
+    reason: ScanReason,
+    paused: bool,
+) {
+    let (max_age_hours, pause_mode) = {
+        let settings = state.settings.lock().unwrap();
+        (settings.catch_up_max_age_hours, settings.pause_mode)
+    };
+    if paused && pause_mode == PauseMode::Skip {
+        println!("⏸️ Paused, skipping catch-up scan ({:?})", reason);
+        return;
+    }
+    let max_age = match max_age_hours {
+        0 => None,
+        hours => Some(Duration::from_secs(hours * 60 * 60)),
+    };
+
+    let dirs: Vec<PathBuf> = active_dirs.iter().cloned().collect();
+    let mut files = find_unnamed_screenshots(&dirs, matcher, max_age);
+    files.retain(|path| !state.ledger.is_processed(path));
+    println!(
+        "🔍 Catch-up scan ({:?}) found {} screenshot(s)",
+        reason,
+        files.len()
+    );
+
+    for path in &files {
+        if paused {
+            defer_screenshot(app_handle, state, path.clone());
+        } else {
+            state.queue.enqueue(path.clone());
+        }
+    }
+
+    let report = CatchUpReport {
+        reason,
+        scanned_at: Utc::now(),
+        files,
+    };
+    *state.catch_up.lock().unwrap() = Some(report.clone());
+    if let Err(e) = app_handle.emit_to("main", "catch-up-scan", report) {
+        eprintln!("❌ Failed to emit event: {:?}", e);
+    }
+}
+
+fn add_watch(watcher: &mut RecommendedWatcher, active_dirs: &mut HashSet<PathBuf>, dir: PathBuf) {
+    if active_dirs.contains(&dir) {
+        return;
+    }
+    match watcher.watch(&dir, RecursiveMode::NonRecursive) {
+        Ok(_) => {
+            println!("📸 Watching screenshots in: {}", dir.display());
+            active_dirs.insert(dir);
+        }
+        Err(e) => eprintln!("❌ Failed to watch {}: {:?}", dir.display(), e),
+    }
+}
+
+fn remove_watch(watcher: &mut RecommendedWatcher, active_dirs: &mut HashSet<PathBuf>, dir: &Path) {
+    if !active_dirs.remove(dir) {
+        return;
+    }
+    match watcher.unwatch(dir) {
+        Ok(_) => println!("🛑 Stopped watching {}", dir.display()),
+        Err(e) => eprintln!("❌ Failed to unwatch {}: {:?}", dir.display(), e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::folders::{add_folder, set_folder_enabled};
+
+    fn next_command(rx: &mpsc::Receiver<WatcherMessage>) -> WatcherCommand {
+        match rx.try_recv() {
+            Ok(WatcherMessage::Control(command)) => command,
+            _ => panic!("expected a control command"),
+        }
+    }
+
+    #[test]
+    fn test_toggling_a_folder_sends_its_canonical_path() {
+        let dir = PathBuf::from("tests/assets/watch_toggle");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let canonical = dir.canonicalize().unwrap();
+        let mut folders = Vec::new();
+        add_folder(&mut folders, &dir.to_string_lossy()).unwrap();
+        let (control, rx) = watcher_channel();
+
+        // The UI may pass the folder spelled differently from the stored path
+        let spelled = format!("{}/", dir.display());
+        for enabled in [false, true] {
+            let folder = set_folder_enabled(&mut folders, &spelled, enabled).unwrap();
+            control.send(WatcherCommand::for_folder(folder));
+        }
+
+        assert!(matches!(next_command(&rx), WatcherCommand::Unwatch(path) if path == canonical));
+        assert!(matches!(next_command(&rx), WatcherCommand::Watch(path) if path == canonical));
+        assert!(rx.try_recv().is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
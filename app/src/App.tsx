@@ -12,7 +12,7 @@ import {
 import {enqueueNotification, notifyFreeLimit} from "./initNotifications";
 import {useLiveUser} from "./hooks/useLiveUser";
 
-import {message, open} from '@tauri-apps/plugin-dialog';
+import {message} from '@tauri-apps/plugin-dialog';
 import {ask} from '@tauri-apps/plugin-dialog';
 
 
@@ -51,12 +51,24 @@ function App() {
   useEffect(() => {
     let unlistenRename: (() => void) | null = null;
     let unlistenQuota: (() => void) | null = null;
+    let unlistenPause: (() => void) | null = null;
 
     const setupListeners = async () => {
       try {
-        unlistenRename = await listen<string>('screenshot-renamed', async (event) => {
-          debug(`📣 Screenshot renamed: ${event.payload}`);
-          setLastFileRenamed(event.payload);
+        unlistenRename = await listen<{
+          status: "renamed" | "kept" | "failed";
+          name: string;
+          originalPath: string;
+          path: string | null;
+          error: string | null;
+        }>('screenshot-renamed', async (event) => {
+          const {status, name, originalPath, path, error} = event.payload;
+          if (status !== "renamed") {
+            warn(`⏭️ Screenshot not renamed (${status}): ${originalPath}${error ? ` - ${error}` : ""}`);
+            return;
+          }
+          debug(`📣 Screenshot renamed: ${path ?? name}`);
+          setLastFileRenamed(name);
         });
 
         unlistenQuota = await listen<string>('quota-exceeded', async (event) => {
@@ -65,6 +77,11 @@ function App() {
           await notifyFreeLimit();
         });
 
+        // Timers and quiet hours pause and resume the watcher on their own
+        unlistenPause = await listen<{paused: boolean}>('pause-changed', (event) => {
+          setPaused(event.payload.paused);
+        });
+
       } catch (err) {
         logError(`Failed to setup event listeners: ${err}`);
       }
@@ -75,6 +92,7 @@ function App() {
     return () => {
       if (unlistenRename) unlistenRename();
       if (unlistenQuota) unlistenQuota();
+      if (unlistenPause) unlistenPause();
     };
   }, []);
   
@@ -95,23 +113,13 @@ function App() {
 
     
 
-    const folder = await open({
-      directory: true,
-      multiple: false,
-      title: "Select Folder to Watch",
-      filters: [{
-        name: "Folders",
-        extensions: ["*"],
-      }],
-    })
-    // const path = await invoke("select_folder");
-    // if (!path) {
-    //   warn("No folder selected");
-    //   return;
-    // }
-    // info(`📂 Folder selected: ${path}`);
-
-    // info("📂 Folder selected for watching");
+    const path = await invoke<string | null>("select_folder");
+    if (!path) {
+      warn("No folder selected");
+      return;
+    }
+    info(`📂 Folder selected: ${path}`);
+
   }
 
 